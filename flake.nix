@@ -43,7 +43,7 @@
         commonArgs = {
           inherit src buildInputs;
           strictDeps = true;
-          cargoExtraArgs = "--locked --no-default-features";
+          cargoExtraArgs = "--locked --no-default-features --features game";
           CARGO_BUILD_TARGET = "wasm32-unknown-unknown";
         };
         cargoArtifacts = craneLib.buildDepsOnly (commonArgs // {
@@ -0,0 +1,430 @@
+//! The quiz in a terminal, for drilling over SSH or where the game window won't start. Uses
+//! the same decks, engine and saved progress as the game.
+//!
+//! `tui export csv` or `tui export jsonl` prints the answer history instead. Built without
+//! Bevy by `cargo run --no-default-features --features tui --bin tui`.
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use crossterm::{
+    cursor::{MoveTo, MoveToColumn},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use rand::rngs::ThreadRng;
+use sinhala_training::{
+    deck::{self, Deck, DECK_FILES},
+    distractors::Confusions,
+    engine::{
+        next_preset, ActiveSetSize, AnswerMode, ChoiceCount, Outcome, Question, QuizEngine,
+        SchedulingMode,
+    },
+    history::{Attempt, ExportFormat},
+    ime,
+    matching::{MatchResult, Strictness},
+    pair::Field,
+    persistence::{self, SaveData},
+    preferences::Preferences,
+    scheduler,
+    transliteration::RomanizationScheme,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+const COLUMNS: usize = 5;
+
+struct Tui {
+    quiz: QuizEngine,
+    /// Kept to be saved back, of which only the deck applies here.
+    preferences: Preferences,
+    decks: Vec<Deck>,
+    deck: usize,
+    question: Option<Question>,
+    input: String,
+    feedback: Option<Outcome>,
+    /// Last error worth showing, like a failed save.
+    status: Option<String>,
+    rng: ThreadRng,
+}
+
+impl Tui {
+    fn new(save: SaveData, decks: Vec<Deck>) -> Self {
+        let active_set = save.active_set.clone();
+        let preferences = save.preferences.clone();
+        let deck = preferences
+            .deck
+            .as_ref()
+            .and_then(|name| decks.iter().position(|deck| &deck.name == name))
+            .unwrap_or_default();
+        let mut tui = Self {
+            quiz: save.into_engine(),
+            preferences,
+            decks,
+            deck,
+            question: None,
+            input: String::new(),
+            feedback: None,
+            status: None,
+            rng: rand::thread_rng(),
+        };
+        match persistence::load_history() {
+            Ok(history) => tui.quiz.set_confusions(Confusions::from_history(&history)),
+            Err(err) => tui.status = Some(err.to_string()),
+        }
+        tui.quiz.set_cards(tui.decks[deck].cards.clone());
+        tui.quiz.restore_active_set(&active_set);
+        tui.quiz.start_session(scheduler::now());
+        tui.next_question();
+        tui
+    }
+
+    fn next_question(&mut self) {
+        self.question = self.quiz.next_question(&mut self.rng, scheduler::now());
+        self.input.clear();
+    }
+
+    fn save(&mut self) {
+        if let Err(err) = persistence::save(&SaveData::from_engine(&self.quiz, &self.preferences)) {
+            self.status = Some(err.to_string());
+        }
+    }
+
+    /// What the typed input stands for, composed into Sinhala when that is what's asked for.
+    fn typed_answer(&self) -> String {
+        if self.quiz.quiz_mode.answer_field == Field::Sinhala {
+            ime::transliterate(&self.input)
+        } else {
+            self.input.clone()
+        }
+    }
+
+    fn submit(&mut self) {
+        let answer = match self.quiz.answer_mode {
+            AnswerMode::MultipleChoice => {
+                let options = self.quiz.options();
+                let Some(answer) = self
+                    .input
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| options.get(number.checked_sub(1)?))
+                else {
+                    self.input.clear();
+                    return;
+                };
+                answer.clone()
+            }
+            AnswerMode::Typed => self.typed_answer(),
+        };
+        if let Some(outcome) = self.quiz.submit_answer(&answer, scheduler::now()) {
+            if let Err(err) = persistence::record(&Attempt::from(&outcome)) {
+                self.status = Some(err.to_string());
+            }
+            self.feedback = Some(outcome);
+            self.save();
+            self.next_question();
+        }
+    }
+
+    /// Applies a setting change, asking a fresh question if it changes what is asked.
+    fn change_setting(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::F(1) => {
+                self.quiz.answer_mode = match self.quiz.answer_mode {
+                    AnswerMode::MultipleChoice => AnswerMode::Typed,
+                    AnswerMode::Typed => AnswerMode::MultipleChoice,
+                };
+            }
+            KeyCode::F(2) => self.quiz.switch_quiz_mode(),
+            KeyCode::F(3) => {
+                self.quiz.scheduling_mode = match self.quiz.scheduling_mode {
+                    SchedulingMode::SpacedRepetition => SchedulingMode::Leitner,
+                    SchedulingMode::Leitner => SchedulingMode::SpacedRepetition,
+                };
+            }
+            KeyCode::F(4) => {
+                self.deck = (self.deck + 1) % self.decks.len();
+                self.quiz.set_cards(self.decks[self.deck].cards.clone());
+                self.preferences.deck = Some(self.decks[self.deck].name.clone());
+            }
+            KeyCode::F(5) => self.quiz.reroll(&mut self.rng),
+            KeyCode::F(6) => {
+                self.quiz.romanization =
+                    next_preset(&RomanizationScheme::ALL, self.quiz.romanization);
+            }
+            KeyCode::F(7) => {
+                self.quiz.strictness = match self.quiz.strictness {
+                    Strictness::Strict => Strictness::Lenient,
+                    Strictness::Lenient => Strictness::Strict,
+                };
+            }
+            KeyCode::F(8) => {
+                let next = next_preset(&ChoiceCount::PRESETS, self.quiz.choice_count());
+                self.quiz.set_choice_count(next);
+            }
+            KeyCode::F(9) => {
+                let next = next_preset(&ActiveSetSize::PRESETS, self.quiz.active_set_size());
+                self.quiz.set_active_set_size(next);
+            }
+            _ => return,
+        }
+        self.feedback = None;
+        self.save();
+        self.next_question();
+    }
+
+    /// Handles a key press, returning `false` once the learner wants to quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Enter => self.submit(),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            code => self.change_setting(code),
+        }
+        true
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+
+        let stats = self.quiz.stats();
+        let boxes = stats
+            .leitner_boxes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        line(
+            out,
+            Color::DarkGrey,
+            &format!(
+                "{} | {} | {} | {} | {} | {} | {} choices | {}",
+                self.decks[self.deck].name,
+                self.quiz.quiz_mode.label(),
+                self.quiz.answer_mode.label(),
+                self.quiz.scheduling_mode.label(),
+                self.quiz.romanization.label(),
+                self.quiz.strictness.label(),
+                self.quiz.choice_count().label(),
+                self.quiz.active_set_size().label(),
+            ),
+        )?;
+        let response_time = stats
+            .average_response_time()
+            .map(|time| format!(", {time:.1} s per answer"))
+            .unwrap_or_default();
+        line(
+            out,
+            Color::DarkGrey,
+            &format!(
+                "Answered {}, correct {}, streak {}{response_time} | Leitner boxes: {boxes}",
+                stats.answered, stats.correct, stats.streak
+            ),
+        )?;
+        line(out, Color::Reset, "")?;
+
+        if let Some(outcome) = &self.feedback {
+            let (color, text) = match outcome.result {
+                MatchResult::Correct => (
+                    Color::Green,
+                    format!(
+                        "Correct: {} ({:.1} s)",
+                        outcome.answer, outcome.response_time
+                    ),
+                ),
+                MatchResult::Close => (
+                    Color::Yellow,
+                    format!("Close: {}, it's {}", outcome.answer, outcome.correct_answer),
+                ),
+                MatchResult::Incorrect => (
+                    Color::Red,
+                    format!("Wrong: {}, it's {}", outcome.answer, outcome.correct_answer),
+                ),
+            };
+            line(out, color, &text)?;
+        } else {
+            line(out, Color::Reset, "")?;
+        }
+        line(out, Color::Reset, "")?;
+
+        let Some(question) = &self.question else {
+            line(out, Color::Reset, "No cards in this deck.")?;
+            return self.draw_help(out);
+        };
+        line(out, Color::Cyan, &question.prompt)?;
+        line(out, Color::DarkGrey, &question.hint)?;
+        line(out, Color::Reset, "")?;
+
+        match self.quiz.answer_mode {
+            AnswerMode::MultipleChoice => {
+                draw_grid(out, &question.options)?;
+                line(out, Color::Reset, "")?;
+                line(out, Color::Reset, &format!("Number: {}", self.input))?;
+            }
+            AnswerMode::Typed => {
+                let mut prompt = format!("Answer: {}", self.input);
+                if self.quiz.quiz_mode.answer_field == Field::Sinhala && !self.input.is_empty() {
+                    prompt += &format!("  ({})", self.typed_answer());
+                }
+                line(out, Color::Reset, &prompt)?;
+            }
+        }
+        self.draw_help(out)
+    }
+
+    fn draw_help(&self, out: &mut impl Write) -> io::Result<()> {
+        line(out, Color::Reset, "")?;
+        if let Some(status) = &self.status {
+            line(out, Color::Red, status)?;
+        }
+        line(
+            out,
+            Color::DarkGrey,
+            "F1 answer mode  F2 quiz mode  F3 scheduling  F4 deck  F5 reroll  \
+             F6 romanization  F7 strictness  F8 choices  F9 set size  Esc quit",
+        )
+    }
+}
+
+/// Prints a line in raw mode, which doesn't return to the first column by itself.
+fn line(out: &mut impl Write, color: Color, text: &str) -> io::Result<()> {
+    queue!(
+        out,
+        SetForegroundColor(color),
+        Print(text),
+        ResetColor,
+        Print("\r\n")
+    )
+}
+
+/// Numbered options in columns. Cells are placed by column rather than padded, as terminals
+/// disagree on how wide Sinhala clusters are.
+fn draw_grid(out: &mut impl Write, options: &[String]) -> io::Result<()> {
+    let width = options
+        .iter()
+        .map(|option| option.graphemes(true).count())
+        .max()
+        .unwrap_or_default()
+        + 6;
+    for (row, options) in options.chunks(COLUMNS).enumerate() {
+        for (column, option) in options.iter().enumerate() {
+            let number = row * COLUMNS + column + 1;
+            queue!(
+                out,
+                MoveToColumn((column * width) as u16),
+                Print(format!("{number:>2}. {option}"))
+            )?;
+        }
+        queue!(out, Print("\r\n"))?;
+    }
+    Ok(())
+}
+
+/// Where the game would look for its assets, so both read the same deck files.
+fn asset_root() -> PathBuf {
+    std::env::var_os("BEVY_ASSET_ROOT")
+        .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR"))
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(PathBuf::from))
+        })
+        .unwrap_or_default()
+        .join("assets")
+}
+
+fn load_decks() -> Vec<Deck> {
+    let root = asset_root();
+    let mut decks = Vec::new();
+    for file in DECK_FILES {
+        let path = root.join(file);
+        let deck = std::fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| Deck::from_ron(&bytes).map_err(|err| err.to_string()));
+        match deck {
+            Ok(deck) => decks.push(deck),
+            Err(err) => eprintln!("Could not load {}: {err}", path.display()),
+        }
+    }
+    decks.extend(deck::generated());
+    decks
+}
+
+fn run(tui: &mut Tui) -> io::Result<()> {
+    let mut out = io::stdout();
+    loop {
+        tui.draw(&mut out)?;
+        out.flush()?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !tui.handle_key(key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Prints the answer history for analysis.
+fn export(format: Option<&str>) -> ExitCode {
+    let Some(format) = ExportFormat::ALL
+        .into_iter()
+        .find(|candidate| Some(candidate.extension()) == format)
+    else {
+        eprintln!("Usage: tui export csv|jsonl");
+        return ExitCode::FAILURE;
+    };
+    let text = persistence::load_history()
+        .map_err(|err| err.to_string())
+        .and_then(|attempts| format.render(&attempts).map_err(|err| err.to_string()));
+    match text {
+        Ok(text) => {
+            print!("{text}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("export") {
+        return export(args.get(1).map(String::as_str));
+    }
+
+    match quiz() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn quiz() -> io::Result<()> {
+    let save = persistence::load()
+        .unwrap_or_else(|err| {
+            eprintln!("Discarding unreadable progress: {err}");
+            None
+        })
+        .unwrap_or_default();
+    let mut tui = Tui::new(save, load_decks());
+
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let result = run(&mut tui);
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
@@ -0,0 +1,92 @@
+//! Special written forms joined with a ZWJ: rakāransaya (ක්‍ර), yansaya (ක්‍ය), repaya (ර්‍ක)
+//! and touching letters (ක්‍ෂ).
+
+use crate::{deck::Deck, inventory, pair::Pair};
+
+const HAL_KIRIMA: char = '\u{0DCA}';
+const ZWJ: char = '\u{200D}';
+
+const RAKARANSAYA: &[char] = &[
+    'ක', 'ග', 'ට', 'ඩ', 'ත', 'ද', 'ධ', 'ප', 'බ', 'භ', 'ම', 'ශ', 'ස', 'ෆ',
+];
+const YANSAYA: &[char] = &[
+    'ක', 'ග', 'ත', 'ද', 'ධ', 'න', 'ප', 'බ', 'භ', 'ම', 'ල', 'ව', 'ශ', 'ස', 'හ',
+];
+const REPAYA: &[char] = &['ක', 'ග', 'ණ', 'ථ', 'ද', 'ධ', 'ම', 'ය', 'ව', 'ශ', 'ෂ'];
+const TOUCHING: &[(char, char)] = &[
+    ('ක', 'ෂ'),
+    ('ක', 'ව'),
+    ('ට', 'ඨ'),
+    ('ත', 'ථ'),
+    ('ත', 'ව'),
+    ('ද', 'ධ'),
+    ('ද', 'ව'),
+    ('න', 'ථ'),
+    ('න', 'ද'),
+    ('න', 'ධ'),
+    ('න', 'ව'),
+    ('ඤ', 'ච'),
+];
+
+/// Joins two consonants as `first + ් + ZWJ + second`.
+pub fn conjunct(first: char, second: char) -> Option<Pair> {
+    if !inventory::letter(first)?.is_consonant() || !inventory::letter(second)?.is_consonant() {
+        return None;
+    }
+    Some(Pair::new(format!("{first}{HAL_KIRIMA}{ZWJ}{second}")))
+}
+
+fn deck(name: &str, pairs: impl IntoIterator<Item = (char, char)>) -> Deck {
+    Deck {
+        name: name.into(),
+        cards: pairs
+            .into_iter()
+            .filter_map(|(first, second)| conjunct(first, second))
+            .collect(),
+    }
+}
+
+pub fn decks() -> Vec<Deck> {
+    let rakaransaya = || RAKARANSAYA.iter().map(|&c| (c, 'ර'));
+    let yansaya = || YANSAYA.iter().map(|&c| (c, 'ය'));
+    let repaya = || REPAYA.iter().map(|&c| ('ර', c));
+    let touching = || TOUCHING.iter().copied();
+
+    vec![
+        deck(
+            "All conjuncts",
+            rakaransaya()
+                .chain(yansaya())
+                .chain(repaya())
+                .chain(touching()),
+        ),
+        deck("Rakāransaya", rakaransaya()),
+        deck("Yansaya", yansaya()),
+        deck("Repaya", repaya()),
+        deck("Touching letters", touching()),
+    ]
+}
+
+/// Shapes every conjunct with the given font and returns the ones that don't come out as
+/// a single glyph cluster, which means the font has no joined form for them.
+#[cfg(feature = "dev_native")]
+pub fn unshaped(font_data: &[u8]) -> Vec<Pair> {
+    let Some(face) = rustybuzz::Face::from_slice(font_data, 0) else {
+        return Vec::new();
+    };
+
+    decks()
+        .swap_remove(0)
+        .cards
+        .into_iter()
+        .filter(|pair| {
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(pair.id());
+            let glyphs = rustybuzz::shape(&face, &[], buffer);
+            glyphs
+                .glyph_infos()
+                .iter()
+                .any(|info| info.cluster != glyphs.glyph_infos()[0].cluster)
+        })
+        .collect()
+}
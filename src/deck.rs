@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+use crate::{conjuncts, inventory, pair::Pair, syllables};
+
+/// Deck files under `assets/`, in the order they are offered.
+pub const DECK_FILES: &[&str] = &["decks/letters.deck.ron", "decks/words.deck.ron"];
+
+/// A named list of cards, either read from a `.deck.ron` file or generated.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Deck {
+    pub name: String,
+    pub cards: Vec<Pair>,
+}
+
+impl Deck {
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, ron::error::SpannedError> {
+        ron::de::from_bytes(bytes)
+    }
+}
+
+/// The letter, syllable and conjunct decks, which are generated instead of loaded.
+pub fn generated() -> Vec<Deck> {
+    inventory::decks()
+        .into_iter()
+        .chain(syllables::decks())
+        .chain(conjuncts::decks())
+        .collect()
+}
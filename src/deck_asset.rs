@@ -0,0 +1,112 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext, LoadState},
+    prelude::*,
+};
+use sinhala_training::deck::{self, Deck, DECK_FILES};
+use thiserror::Error;
+
+/// A [`Deck`] as a Bevy asset, loaded from a `.deck.ron` file under `assets/decks/`.
+#[derive(Debug, Asset, TypePath, Deref)]
+pub struct DeckAsset(pub Deck);
+
+/// The deck being quizzed.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct ActiveDeck(pub Handle<DeckAsset>);
+/// The deck files followed by the generated decks.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct AvailableDecks(pub Vec<Handle<DeckAsset>>);
+/// Name of the deck chosen in an earlier session, made the [`ActiveDeck`] once the decks have
+/// loaded far enough to find it.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct RestoredDeck(pub String);
+
+#[derive(Debug, Error)]
+pub enum DeckLoaderError {
+    #[error("Could not read deck: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse deck: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+#[derive(Default)]
+pub struct DeckLoader;
+
+impl AssetLoader for DeckLoader {
+    type Asset = DeckAsset;
+    type Settings = ();
+    type Error = DeckLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(DeckAsset(Deck::from_ron(&bytes)?))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["deck.ron"]
+    }
+}
+
+pub struct DeckPlugin;
+
+impl Plugin for DeckPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DeckAsset>()
+            .init_asset_loader::<DeckLoader>()
+            .add_systems(PreStartup, load_decks)
+            .add_systems(
+                PreUpdate,
+                restore_deck.run_if(resource_exists::<RestoredDeck>),
+            );
+    }
+}
+
+fn load_decks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut decks: ResMut<Assets<DeckAsset>>,
+) {
+    let mut available = DECK_FILES
+        .iter()
+        .map(|&path| asset_server.load(path))
+        .collect::<Vec<_>>();
+    available.extend(
+        deck::generated()
+            .into_iter()
+            .map(|deck| decks.add(DeckAsset(deck))),
+    );
+
+    commands.insert_resource(ActiveDeck(available[0].clone()));
+    commands.insert_resource(AvailableDecks(available));
+}
+
+fn restore_deck(
+    mut commands: Commands,
+    restored_deck: Res<RestoredDeck>,
+    asset_server: Res<AssetServer>,
+    decks: Res<Assets<DeckAsset>>,
+    available_decks: Res<AvailableDecks>,
+    mut active_deck: ResMut<ActiveDeck>,
+) {
+    let mut settled = true;
+    for handle in available_decks.iter() {
+        match decks.get(handle) {
+            Some(deck) if deck.name == restored_deck.0 => {
+                active_deck.0 = handle.clone();
+                commands.remove_resource::<RestoredDeck>();
+                return;
+            }
+            Some(_) => {}
+            None => settled &= matches!(asset_server.load_state(handle), LoadState::Failed(_)),
+        }
+    }
+    // Every deck is loaded or failed to, and the one we're after isn't among them.
+    if settled {
+        commands.remove_resource::<RestoredDeck>();
+    }
+}
@@ -0,0 +1,145 @@
+//! Picks the wrong options of a multiple choice question, favouring the ones most likely to
+//! be mistaken for the answer: letters that look alike, and cards the learner has mixed up
+//! before.
+
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{history::Attempt, pair::Pair};
+
+/// Letters that are easily told apart only by a loop, a stroke or a tail.
+const LOOK_ALIKES: &[&[char]] = &[
+    &['ප', 'ෂ'],
+    &['බ', 'ඛ'],
+    &['ද', 'ඳ'],
+    &['ග', 'ශ'],
+    &['ඩ', 'ඬ'],
+    &['ම', 'ඹ'],
+    &['ට', 'ඨ'],
+    &['ත', 'න', 'ණ'],
+    &['ල', 'ළ'],
+    &['ව', 'ච', 'ජ'],
+    &['ය', 'ඝ'],
+    &['ස', 'හ'],
+    &['ඉ', 'ඊ'],
+    &['උ', 'ඌ'],
+    &['එ', 'ඒ'],
+    &['ඔ', 'ඕ'],
+    &['අ', 'ආ', 'ඇ', 'ඈ'],
+];
+/// How much more likely a card is picked for each look-alike letter it shares with the answer.
+const LOOK_ALIKE_WEIGHT: f64 = 2.0;
+/// How much more likely a card is picked for each time it was mistaken for the answer.
+const CONFUSION_WEIGHT: f64 = 3.0;
+
+fn look_alike(a: char, b: char) -> bool {
+    a != b
+        && LOOK_ALIKES
+            .iter()
+            .any(|group| group.contains(&a) && group.contains(&b))
+}
+
+/// How similar two cards look: each look-alike letter in the same place counts twice, and
+/// each identical one once, so බා and ඛා are closer than බා and බි.
+pub fn similarity(a: &Pair, b: &Pair) -> u32 {
+    a.id()
+        .chars()
+        .zip(b.id().chars())
+        .map(|(a, b)| {
+            if a == b {
+                1
+            } else if look_alike(a, b) {
+                2
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// How often each pair of cards was mixed up, in either direction.
+#[derive(Debug, Default, Clone)]
+pub struct Confusions {
+    counts: HashMap<(String, String), u32>,
+}
+
+impl Confusions {
+    pub fn from_history<'a>(attempts: impl IntoIterator<Item = &'a Attempt>) -> Self {
+        let mut confusions = Self::default();
+        for attempt in attempts {
+            if let Some(chosen) = &attempt.chosen_card_id {
+                confusions.record(&attempt.card_id, chosen);
+            }
+        }
+        confusions
+    }
+
+    fn key(a: &str, b: &str) -> (String, String) {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        (first.to_owned(), second.to_owned())
+    }
+
+    /// Notes that the card `chosen` was answered for the card `asked`. Picking the right card
+    /// isn't a confusion, and is ignored.
+    pub fn record(&mut self, asked: &str, chosen: &str) {
+        if asked != chosen {
+            *self.counts.entry(Self::key(asked, chosen)).or_default() += 1;
+        }
+    }
+
+    pub fn count(&self, a: &Pair, b: &Pair) -> u32 {
+        self.counts
+            .get(&Self::key(a.id(), b.id()))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Draws up to `count` distractors for `answer` from `candidates`, weighted toward
+/// look-alikes and past confusions, but leaving every candidate a chance.
+pub fn pick<'a>(
+    answer: &Pair,
+    candidates: &[&'a Pair],
+    count: usize,
+    confusions: &Confusions,
+    rng: &mut impl Rng,
+) -> Vec<&'a Pair> {
+    let weight = |candidate: &&Pair| {
+        1.0 + LOOK_ALIKE_WEIGHT * f64::from(similarity(answer, candidate))
+            + CONFUSION_WEIGHT * f64::from(confusions.count(answer, candidate))
+    };
+    candidates
+        .choose_multiple_weighted(rng, count, weight)
+        .map(|picked| picked.copied().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(sinhala: &str) -> Pair {
+        Pair::new(sinhala.to_owned())
+    }
+
+    #[test]
+    fn look_alikes_are_more_similar_than_other_letters() {
+        assert_eq!(similarity(&card("බා"), &card("ඛා")), 3);
+        assert_eq!(similarity(&card("බා"), &card("බි")), 1);
+        assert_eq!(similarity(&card("බා"), &card("කි")), 0);
+        assert_eq!(similarity(&card("ත"), &card("ණ")), 2);
+    }
+
+    #[test]
+    fn confusions_count_both_directions() {
+        let mut confusions = Confusions::default();
+        confusions.record("ත", "න");
+        confusions.record("න", "ත");
+        confusions.record("ත", "ත");
+        assert_eq!(confusions.count(&card("ත"), &card("න")), 2);
+        assert_eq!(confusions.count(&card("න"), &card("ත")), 2);
+        assert_eq!(confusions.count(&card("ත"), &card("ත")), 0);
+        assert_eq!(confusions.count(&card("ත"), &card("ණ")), 0);
+    }
+}
@@ -0,0 +1,690 @@
+//! The rules of the quiz, independent of how it is shown: which card comes next, whether an
+//! answer is right and how that changes the schedule.
+
+use rand::{prelude::SliceRandom, seq::IteratorRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    distractors::{self, Confusions},
+    leitner::{Leitner, BOXES},
+    matching::{self, MatchResult, Strictness},
+    pair::{Pair, QuizMode},
+    scheduler::Scheduler,
+    transliteration::RomanizationScheme,
+};
+
+/// How many cards are quizzed at once, unless more options are shown than that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveSetSize(pub usize);
+
+impl Default for ActiveSetSize {
+    fn default() -> Self {
+        ActiveSetSize(25)
+    }
+}
+
+impl ActiveSetSize {
+    /// The sizes to choose from in the settings.
+    pub const PRESETS: [ActiveSetSize; 4] = [
+        ActiveSetSize(10),
+        ActiveSetSize(25),
+        ActiveSetSize(50),
+        ActiveSetSize(100),
+    ];
+
+    pub fn label(&self) -> String {
+        format!("{} cards", self.0)
+    }
+}
+
+/// The preset after `current` in one of the `PRESETS` lists, wrapping around, or the first if
+/// `current` isn't one of them. For cycling through a setting in the front ends.
+pub fn next_preset<T: Copy + PartialEq>(presets: &[T], current: T) -> T {
+    match presets.iter().position(|&preset| preset == current) {
+        Some(index) => presets[(index + 1) % presets.len()],
+        None => presets[0],
+    }
+}
+
+/// How many options a multiple choice question offers, the answer included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChoiceCount {
+    Options(usize),
+    /// Every card of the deck, which all become the active set.
+    WholeDeck,
+}
+
+impl Default for ChoiceCount {
+    fn default() -> Self {
+        ChoiceCount::Options(6)
+    }
+}
+
+impl ChoiceCount {
+    /// The counts to choose from in the settings.
+    pub const PRESETS: [ChoiceCount; 6] = [
+        ChoiceCount::Options(4),
+        ChoiceCount::Options(6),
+        ChoiceCount::Options(9),
+        ChoiceCount::Options(16),
+        ChoiceCount::Options(25),
+        ChoiceCount::WholeDeck,
+    ];
+
+    pub fn label(&self) -> String {
+        match *self {
+            ChoiceCount::Options(options) => options.to_string(),
+            ChoiceCount::WholeDeck => "All".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SchedulingMode {
+    #[default]
+    SpacedRepetition,
+    Leitner,
+}
+
+impl SchedulingMode {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            SchedulingMode::SpacedRepetition => "SRS",
+            SchedulingMode::Leitner => "Leitner",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnswerMode {
+    #[default]
+    MultipleChoice,
+    Typed,
+}
+
+impl AnswerMode {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            AnswerMode::MultipleChoice => "Choice",
+            AnswerMode::Typed => "Typed",
+        }
+    }
+}
+
+/// When a session ends. Sessions measured in time let the current question be answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionLength {
+    Questions(usize),
+    Minutes(u32),
+}
+
+impl Default for SessionLength {
+    fn default() -> Self {
+        SessionLength::Questions(20)
+    }
+}
+
+impl SessionLength {
+    /// The lengths to choose from in the settings.
+    pub const PRESETS: [SessionLength; 5] = [
+        SessionLength::Questions(10),
+        SessionLength::Questions(20),
+        SessionLength::Questions(50),
+        SessionLength::Minutes(5),
+        SessionLength::Minutes(10),
+    ];
+
+    pub fn label(&self) -> String {
+        match *self {
+            SessionLength::Questions(questions) => format!("{questions} questions"),
+            SessionLength::Minutes(minutes) => format!("{minutes} min"),
+        }
+    }
+}
+
+/// A run of questions from starting the quiz until its [`SessionLength`] is reached.
+#[derive(Debug, Default, Clone)]
+pub struct Session {
+    pub length: SessionLength,
+    pub started_at: f64,
+    /// Cards answered wrongly at least once, in the order they were first missed.
+    pub missed: Vec<Pair>,
+    /// Cards still to be asked when retrying the misses of the previous session.
+    retrying: Option<Vec<Pair>>,
+}
+
+/// The card being asked, as it should be shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Question {
+    pub pair: Pair,
+    pub prompt: String,
+    /// Part of speech and tags, empty for cards without them.
+    pub hint: String,
+    /// The answer and a few distractors from the active set, shuffled, to choose from.
+    pub options: Vec<String>,
+}
+
+/// What came of submitting an answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub pair: Pair,
+    pub quiz_mode: QuizMode,
+    pub answer_mode: AnswerMode,
+    pub answer: String,
+    /// The card of the active set whose answer was given, if any.
+    pub chosen: Option<Pair>,
+    pub correct_answer: String,
+    pub result: MatchResult,
+    /// Seconds from asking the question to answering it.
+    pub response_time: f64,
+    /// Seconds since the unix epoch at which the answer was given.
+    pub answered_at: f64,
+    /// The wrong options that were shown, empty for typed answers.
+    pub distractors: Vec<String>,
+}
+
+impl Outcome {
+    pub fn is_correct(&self) -> bool {
+        self.result == MatchResult::Correct
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Answers given since the session started.
+    pub answered: usize,
+    pub correct: usize,
+    /// Correct answers in a row, up to the last one.
+    pub streak: usize,
+    /// Seconds spent answering, summed over the session.
+    pub response_time: f64,
+    /// How many of the deck's cards are in each Leitner box.
+    pub leitner_boxes: [usize; BOXES],
+}
+
+impl Stats {
+    /// The share of correct answers, between 0 and 1.
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.answered > 0).then(|| self.correct as f64 / self.answered as f64)
+    }
+
+    pub fn average_response_time(&self) -> Option<f64> {
+        (self.answered > 0).then(|| self.response_time / self.answered as f64)
+    }
+}
+
+/// A quiz over one deck at a time. Front ends show what [`QuizEngine::next_question`]
+/// returns, pass what the learner answered to [`QuizEngine::submit_answer`], and change the
+/// settings through the public fields.
+#[derive(Debug, Default, Clone)]
+pub struct QuizEngine {
+    pub quiz_mode: QuizMode,
+    pub scheduling_mode: SchedulingMode,
+    pub answer_mode: AnswerMode,
+    pub strictness: Strictness,
+    pub romanization: RomanizationScheme,
+    /// The length of sessions started from now on.
+    pub session_length: SessionLength,
+    choice_count: ChoiceCount,
+    active_set_size: ActiveSetSize,
+    scheduler: Scheduler,
+    leitner: Leitner,
+    cards: Vec<Pair>,
+    active_set: Vec<Pair>,
+    current: Option<Pair>,
+    /// The cards whose answers are offered for the current question.
+    choices: Vec<Pair>,
+    confusions: Confusions,
+    asked_at: f64,
+    answered: bool,
+    session: Session,
+    stats: Stats,
+}
+
+impl QuizEngine {
+    pub fn new(scheduler: Scheduler, leitner: Leitner) -> Self {
+        Self {
+            scheduler,
+            leitner,
+            ..Default::default()
+        }
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    pub fn leitner(&self) -> &Leitner {
+        &self.leitner
+    }
+
+    /// Replaces what is known about which cards the learner mixes up, usually with
+    /// [`Confusions::from_history`]. Answers given from now on are added to it.
+    pub fn set_confusions(&mut self, confusions: Confusions) {
+        self.confusions = confusions;
+    }
+
+    pub fn choice_count(&self) -> ChoiceCount {
+        self.choice_count
+    }
+
+    /// Changes how many options are offered, growing or shrinking the active set to match.
+    pub fn set_choice_count(&mut self, choice_count: ChoiceCount) {
+        self.choice_count = choice_count;
+        self.resize_active_set();
+    }
+
+    pub fn active_set_size(&self) -> ActiveSetSize {
+        self.active_set_size
+    }
+
+    /// Changes how many cards are quizzed at once, dropping the last added cards of the
+    /// active set or filling it up from the deck.
+    pub fn set_active_set_size(&mut self, active_set_size: ActiveSetSize) {
+        self.active_set_size = active_set_size;
+        self.resize_active_set();
+    }
+
+    fn resize_active_set(&mut self) {
+        self.active_set.truncate(self.active_set_len());
+        self.fill_active_set();
+        self.current = None;
+    }
+
+    /// How many cards the active set holds: the chosen size, or more to offer every option.
+    fn active_set_len(&self) -> usize {
+        match self.choice_count {
+            ChoiceCount::Options(options) => options.max(self.active_set_size.0),
+            ChoiceCount::WholeDeck => self.cards.len(),
+        }
+    }
+
+    /// Every card in the deck.
+    pub fn cards(&self) -> &[Pair] {
+        &self.cards
+    }
+
+    /// The cards currently being quizzed.
+    pub fn active_set(&self) -> &[Pair] {
+        &self.active_set
+    }
+
+    /// Switches to a new deck, or a new version of the same one. Cards of the active set that
+    /// are still in the deck are kept, and the rest of the set is filled up from the deck.
+    pub fn set_cards(&mut self, cards: Vec<Pair>) {
+        self.cards = cards;
+        if !self.quiz_mode.supported_by(&self.cards) {
+            self.quiz_mode = QuizMode::default();
+        }
+        let cards = &self.cards;
+        self.active_set.retain(|card| cards.contains(card));
+        self.fill_active_set();
+        self.current = None;
+    }
+
+    /// Makes the cards with the given ids the active set, for picking up where a previous
+    /// session left off. Ids that aren't in the deck are skipped.
+    pub fn restore_active_set(&mut self, ids: &[String]) {
+        self.active_set = ids
+            .iter()
+            .filter_map(|id| self.cards.iter().find(|card| card.id() == id))
+            .cloned()
+            .collect();
+        self.fill_active_set();
+        self.current = None;
+    }
+
+    fn fill_active_set(&mut self) {
+        let size = self.active_set_len();
+        for card in &self.cards {
+            if self.active_set.len() >= size {
+                break;
+            }
+            if !self.active_set.contains(card) {
+                self.active_set.push(card.clone());
+            }
+        }
+    }
+
+    /// Replaces the active set with a random selection from the deck.
+    pub fn reroll(&mut self, rng: &mut impl Rng) {
+        self.active_set = self
+            .cards
+            .iter()
+            .cloned()
+            .choose_multiple(rng, self.active_set_len());
+        self.active_set.shuffle(rng);
+        self.current = None;
+    }
+
+    /// Moves to the next card of the active set, or of the misses being retried, never asking
+    /// the same card twice in a row unless it is the only one. Returns `None` while there are
+    /// no cards left to ask.
+    pub fn next_question(&mut self, rng: &mut impl Rng, now: f64) -> Option<Question> {
+        let pool = match &self.session.retrying {
+            Some(retrying) => retrying,
+            None => &self.active_set,
+        };
+        let candidates = pool
+            .iter()
+            .filter(|&card| Some(card) != self.current.as_ref());
+        let next = match self.scheduling_mode {
+            SchedulingMode::SpacedRepetition => self.scheduler.next(candidates, rng),
+            SchedulingMode::Leitner => self.leitner.next(candidates, rng),
+        };
+        let pair = next.or_else(|| pool.first())?.clone();
+
+        self.choices = self.pick_choices(&pair, rng);
+        self.current = Some(pair.clone());
+        self.asked_at = now;
+        self.answered = false;
+        Some(Question {
+            prompt: pair.question(self.quiz_mode, self.romanization),
+            hint: pair.hint(),
+            options: self.options(),
+            pair,
+        })
+    }
+
+    /// The card to ask and distractors for it, shuffled. Cards that share the answer's text
+    /// can't be told apart from it, so they are left out, as are repeats of another's text.
+    fn pick_choices(&self, pair: &Pair, rng: &mut impl Rng) -> Vec<Pair> {
+        let answer = pair.answer(self.quiz_mode, self.romanization);
+        let mut seen = vec![answer];
+        let candidates = self
+            .active_set
+            .iter()
+            .filter(|card| {
+                let answer = card.answer(self.quiz_mode, self.romanization);
+                let new = !seen.contains(&answer);
+                seen.push(answer);
+                new
+            })
+            .collect::<Vec<_>>();
+
+        let count = match self.choice_count {
+            ChoiceCount::Options(options) => options.saturating_sub(1),
+            ChoiceCount::WholeDeck => candidates.len(),
+        };
+        let mut choices = distractors::pick(pair, &candidates, count, &self.confusions, rng)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        choices.push(pair.clone());
+        choices.shuffle(rng);
+        choices
+    }
+
+    /// The answers to choose from for the current question.
+    pub fn options(&self) -> Vec<String> {
+        self.choices
+            .iter()
+            .map(|card| card.answer(self.quiz_mode, self.romanization))
+            .collect()
+    }
+
+    /// Which of the [`QuizEngine::options`] is the answer to the current question.
+    pub fn correct_option(&self) -> Option<usize> {
+        let current = self.current.as_ref()?;
+        self.choices.iter().position(|card| card == current)
+    }
+
+    /// Whether the current question has been answered and is waiting for the next one.
+    pub fn is_answered(&self) -> bool {
+        self.current.is_some() && self.answered
+    }
+
+    /// Grades an answer to the current question and reschedules its card. Choices have to
+    /// match exactly, while typed answers are checked with the configured strictness.
+    /// Returns `None` when there is no question or it was already answered.
+    pub fn submit_answer(&mut self, answer: &str, now: f64) -> Option<Outcome> {
+        if self.answered {
+            return None;
+        }
+        let pair = self.current.clone()?;
+        let correct_answer = pair.answer(self.quiz_mode, self.romanization);
+        let result = match self.answer_mode {
+            AnswerMode::MultipleChoice if answer == correct_answer => MatchResult::Correct,
+            AnswerMode::MultipleChoice => MatchResult::Incorrect,
+            AnswerMode::Typed => matching::check(answer, &correct_answer, self.strictness),
+        };
+        let (chosen, distractors) = match self.answer_mode {
+            AnswerMode::MultipleChoice => (
+                self.choices
+                    .iter()
+                    .find(|card| card.answer(self.quiz_mode, self.romanization) == answer),
+                self.options()
+                    .into_iter()
+                    .filter(|option| option != &correct_answer)
+                    .collect(),
+            ),
+            AnswerMode::Typed => (
+                self.active_set.iter().find(|card| {
+                    let card_answer = card.answer(self.quiz_mode, self.romanization);
+                    matching::check(answer, &card_answer, self.strictness) == MatchResult::Correct
+                }),
+                Vec::new(),
+            ),
+        };
+        if let Some(chosen) = chosen {
+            self.confusions.record(pair.id(), chosen.id());
+        }
+        let outcome = Outcome {
+            pair,
+            quiz_mode: self.quiz_mode,
+            answer_mode: self.answer_mode,
+            answer: answer.to_owned(),
+            chosen: chosen.cloned(),
+            correct_answer,
+            result,
+            response_time: now - self.asked_at,
+            answered_at: now,
+            distractors,
+        };
+
+        self.answered = true;
+        self.scheduler.review(
+            &outcome.pair,
+            outcome.is_correct(),
+            outcome.response_time,
+            now,
+        );
+        self.leitner.review(&outcome.pair, outcome.is_correct());
+        self.stats.answered += 1;
+        self.stats.response_time += outcome.response_time;
+        if outcome.is_correct() {
+            self.stats.correct += 1;
+            self.stats.streak += 1;
+        } else {
+            self.stats.streak = 0;
+            if !self.session.missed.contains(&outcome.pair) {
+                self.session.missed.push(outcome.pair.clone());
+            }
+        }
+        if let Some(retrying) = &mut self.session.retrying {
+            retrying.retain(|card| card != &outcome.pair);
+        }
+        Some(outcome)
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Starts a session of [`QuizEngine::session_length`], counting the [`Stats`] afresh.
+    pub fn start_session(&mut self, now: f64) {
+        self.session = Session {
+            length: self.session_length,
+            started_at: now,
+            ..Default::default()
+        };
+        self.stats = Stats::default();
+        self.current = None;
+    }
+
+    /// Starts a session asking each card missed in the current one once more. Misses that
+    /// are no longer in the deck are skipped.
+    pub fn retry_missed(&mut self, now: f64) {
+        let retrying = self
+            .session
+            .missed
+            .iter()
+            .filter(|card| self.cards.contains(card))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.start_session(now);
+        self.session.length = SessionLength::Questions(retrying.len());
+        self.session.retrying = Some(retrying);
+    }
+
+    /// Whether the session has reached its length, and the current question was answered.
+    pub fn is_session_over(&self, now: f64) -> bool {
+        if self.current.is_some() && !self.answered {
+            return false;
+        }
+        match self.session.length {
+            SessionLength::Questions(questions) => self.stats.answered >= questions,
+            SessionLength::Minutes(minutes) => {
+                now - self.session.started_at >= f64::from(minutes) * 60.0
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            leitner_boxes: self.leitner.distribution(&self.cards),
+            ..self.stats
+        }
+    }
+
+    /// Moves to the next quiz mode the deck supports.
+    pub fn switch_quiz_mode(&mut self) {
+        let modes = QuizMode::available(&self.cards);
+        let current = modes.iter().position(|mode| mode == &self.quiz_mode);
+        self.quiz_mode = match current {
+            Some(current) => modes[(current + 1) % modes.len()],
+            None => modes.first().copied().unwrap_or_default(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::deck;
+
+    fn engine() -> (QuizEngine, StdRng) {
+        let mut engine = QuizEngine::new(Scheduler::default(), Leitner::default());
+        engine.set_cards(deck::generated()[0].cards.clone());
+        engine.start_session(0.0);
+        (engine, StdRng::seed_from_u64(7))
+    }
+
+    fn answer_correctly(engine: &mut QuizEngine, question: &Question, now: f64) -> Outcome {
+        let answer = question.options[engine.correct_option().unwrap()].clone();
+        engine.submit_answer(&answer, now).unwrap()
+    }
+
+    #[test]
+    fn never_asks_the_same_card_twice_in_a_row() {
+        let (mut engine, mut rng) = engine();
+        let mut previous = None;
+        for i in 0..50 {
+            let now = f64::from(i);
+            let question = engine.next_question(&mut rng, now).unwrap();
+            assert_ne!(Some(&question.pair), previous.as_ref());
+            answer_correctly(&mut engine, &question, now);
+            previous = Some(question.pair);
+        }
+    }
+
+    #[test]
+    fn answers_count_once() {
+        let (mut engine, mut rng) = engine();
+        let question = engine.next_question(&mut rng, 0.0).unwrap();
+        assert!(answer_correctly(&mut engine, &question, 1.0).is_correct());
+        assert!(engine.is_answered());
+        assert_eq!(engine.submit_answer("", 2.0), None);
+        assert_eq!(engine.stats().answered, 1);
+        assert_eq!(engine.stats().correct, 1);
+    }
+
+    #[test]
+    fn session_ends_after_its_questions() {
+        let (mut engine, mut rng) = engine();
+        engine.session_length = SessionLength::Questions(3);
+        engine.start_session(0.0);
+        for i in 0..3 {
+            assert!(!engine.is_session_over(0.0));
+            let question = engine.next_question(&mut rng, 0.0).unwrap();
+            assert!(!engine.is_session_over(0.0), "question {i} is still open");
+            answer_correctly(&mut engine, &question, 1.0);
+        }
+        assert!(engine.is_session_over(1.0));
+    }
+
+    #[test]
+    fn session_ends_after_its_minutes() {
+        let (mut engine, _) = engine();
+        engine.session_length = SessionLength::Minutes(5);
+        engine.start_session(0.0);
+        assert!(!engine.is_session_over(299.0));
+        assert!(engine.is_session_over(300.0));
+    }
+
+    #[test]
+    fn retry_asks_each_miss_once() {
+        let (mut engine, mut rng) = engine();
+        let mut missed = Vec::new();
+        for i in 0..6 {
+            let now = f64::from(i);
+            let question = engine.next_question(&mut rng, now).unwrap();
+            if i % 2 == 0 {
+                assert!(!engine.submit_answer("wrong", now).unwrap().is_correct());
+                if !missed.contains(&question.pair) {
+                    missed.push(question.pair);
+                }
+            } else {
+                answer_correctly(&mut engine, &question, now);
+            }
+        }
+        assert_eq!(engine.session().missed, missed);
+
+        // Misses are retried even once a reroll has taken them out of the active set.
+        engine.set_active_set_size(ActiveSetSize(10));
+        engine.reroll(&mut rng);
+        engine.retry_missed(10.0);
+        let mut asked = Vec::new();
+        while !engine.is_session_over(10.0) {
+            let question = engine.next_question(&mut rng, 10.0).unwrap();
+            answer_correctly(&mut engine, &question, 10.0);
+            asked.push(question.pair);
+        }
+        asked.sort_by(|a, b| a.id().cmp(b.id()));
+        missed.sort_by(|a, b| a.id().cmp(b.id()));
+        assert_eq!(asked, missed);
+    }
+
+    #[test]
+    fn retry_without_misses_is_over_at_once() {
+        let (mut engine, mut rng) = engine();
+        let question = engine.next_question(&mut rng, 0.0).unwrap();
+        answer_correctly(&mut engine, &question, 0.0);
+        engine.retry_missed(1.0);
+        assert_eq!(engine.next_question(&mut rng, 1.0), None);
+        assert!(engine.is_session_over(1.0));
+    }
+
+    #[test]
+    fn next_preset_wraps_around() {
+        let presets = SessionLength::PRESETS;
+        assert_eq!(next_preset(&presets, presets[0]), presets[1]);
+        assert_eq!(next_preset(&presets, presets[4]), presets[0]);
+        assert_eq!(
+            next_preset(&presets, SessionLength::Questions(7)),
+            presets[0]
+        );
+    }
+}
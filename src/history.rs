@@ -0,0 +1,183 @@
+//! A record of every answer given, for analysing how learning goes outside the app.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{AnswerMode, Outcome},
+    matching::MatchResult,
+    pair::QuizMode,
+};
+
+/// One answer to one question, as stored in the history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    /// Seconds since the unix epoch at which the answer was given.
+    pub timestamp: f64,
+    pub card_id: String,
+    pub quiz_mode: QuizMode,
+    pub answer_mode: AnswerMode,
+    pub answer: String,
+    /// The card whose answer was given, if it belongs to one of the active set.
+    #[serde(default)]
+    pub chosen_card_id: Option<String>,
+    pub correct_answer: String,
+    pub result: MatchResult,
+    /// Seconds from asking the question to answering it.
+    pub response_time: f64,
+    /// The wrong options that were shown, empty for typed answers.
+    pub distractors: Vec<String>,
+}
+
+impl From<&Outcome> for Attempt {
+    fn from(outcome: &Outcome) -> Self {
+        Self {
+            timestamp: outcome.answered_at,
+            card_id: outcome.pair.id().to_owned(),
+            quiz_mode: outcome.quiz_mode,
+            answer_mode: outcome.answer_mode,
+            answer: outcome.answer.clone(),
+            chosen_card_id: outcome.chosen.as_ref().map(|pair| pair.id().to_owned()),
+            correct_answer: outcome.correct_answer.clone(),
+            result: outcome.result,
+            response_time: outcome.response_time,
+            distractors: outcome.distractors.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per line, the way the history is stored.
+    Jsonl,
+    /// A header and one row per attempt, with the distractors separated by `|`.
+    Csv,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Jsonl];
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            ExportFormat::Jsonl => "JSONL",
+            ExportFormat::Csv => "CSV",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    pub fn render(&self, attempts: &[Attempt]) -> Result<String, serde_json::Error> {
+        match *self {
+            ExportFormat::Jsonl => to_jsonl(attempts),
+            ExportFormat::Csv => Ok(to_csv(attempts)),
+        }
+    }
+}
+
+pub fn to_jsonl(attempts: &[Attempt]) -> Result<String, serde_json::Error> {
+    let mut jsonl = String::new();
+    for attempt in attempts {
+        jsonl += &serde_json::to_string(attempt)?;
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+/// Reads attempts stored one per line, skipping blank lines.
+pub fn from_jsonl(jsonl: &str) -> Result<Vec<Attempt>, serde_json::Error> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+pub fn to_csv(attempts: &[Attempt]) -> String {
+    let mut csv = String::from(
+        "timestamp,card_id,prompt_field,answer_field,answer_mode,answer,chosen_card_id,\
+         correct_answer,result,response_time,distractors\n",
+    );
+    for attempt in attempts {
+        let fields = [
+            attempt.timestamp.to_string(),
+            attempt.card_id.clone(),
+            format!("{:?}", attempt.quiz_mode.prompt_field),
+            format!("{:?}", attempt.quiz_mode.answer_field),
+            format!("{:?}", attempt.answer_mode),
+            attempt.answer.clone(),
+            attempt.chosen_card_id.clone().unwrap_or_default(),
+            attempt.correct_answer.clone(),
+            format!("{:?}", attempt.result),
+            attempt.response_time.to_string(),
+            attempt.distractors.join("|"),
+        ];
+        let row = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv += &row;
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quotes a field if it holds anything CSV gives a meaning to.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attempt(answer: &str) -> Attempt {
+        Attempt {
+            timestamp: 1_700_000_000.5,
+            card_id: "ක".to_owned(),
+            quiz_mode: QuizMode::default(),
+            answer_mode: AnswerMode::MultipleChoice,
+            answer: answer.to_owned(),
+            chosen_card_id: Some("ග".to_owned()),
+            correct_answer: "ka".to_owned(),
+            result: MatchResult::Incorrect,
+            response_time: 2.25,
+            distractors: vec!["ga".to_owned(), "ca".to_owned()],
+        }
+    }
+
+    #[test]
+    fn jsonl_round_trips_and_skips_blank_lines() {
+        let attempts = [attempt("ga"), attempt("line\nbreak")];
+        let jsonl = to_jsonl(&attempts).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        assert_eq!(from_jsonl(&format!("{jsonl}\n\n")).unwrap(), attempts);
+    }
+
+    #[test]
+    fn csv_quotes_fields_that_need_it() {
+        assert_eq!(csv_field("ga"), "ga");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"a\""), "\"say \"\"a\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn csv_has_a_row_per_attempt() {
+        let csv = to_csv(&[attempt("g,a")]);
+        let rows = csv.lines().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1],
+            "1700000000.5,ක,Sinhala,Romanization,MultipleChoice,\"g,a\",ග,ka,Incorrect,2.25,ga|ca"
+        );
+    }
+}
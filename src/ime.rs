@@ -0,0 +1,64 @@
+//! Phonetic input method turning Latin keystrokes into Sinhala script.
+//!
+//! Consonants are spelled like the romanizations, with capitals for the retroflex series
+//! ("T" → ට, "N" → ණ) and a caret for the prenasalized ones ("^g" → ඟ). A consonant that
+//! isn't followed by a vowel gets a hal kirīma ("bat" → බත්), and "r"/"y" after a bare
+//! consonant join as rakāransaya/yansaya ("kra" → ක්‍ර). A "+" joins any two consonants
+//! with a ZWJ ("r+ka" → ර්‍ක), which is how න්‍ය is typed, as "nya" is ඤ. A ":" keeps letters
+//! apart that would otherwise read as one ("k:ha" → ක්හ, where "kha" is ඛ).
+
+use crate::transliteration::{RomanizationScheme, Spelling};
+
+/// Transliterates phonetic Latin input into Sinhala. Both the ASCII and the ISO 15919
+/// spellings are understood, and anything else is passed through untouched.
+pub fn transliterate(input: &str) -> String {
+    Spelling::new(&[RomanizationScheme::Ascii, RomanizationScheme::Iso15919])
+        .with_consonant("w", 'ව')
+        .with_consonant("ny", 'ඤ')
+        .with_consonant("jny", 'ඥ')
+        .to_sinhala(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transliterate;
+
+    #[test]
+    fn vowels_follow_the_consonant() {
+        assert_eq!(transliterate("ka"), "ක");
+        assert_eq!(transliterate("kaa"), "කා");
+        assert_eq!(transliterate("ki"), "කි");
+        assert_eq!(transliterate("a"), "අ");
+    }
+
+    #[test]
+    fn bare_consonants_get_hal_kirima() {
+        assert_eq!(transliterate("k"), "ක්");
+        assert_eq!(transliterate("bat"), "බත්");
+    }
+
+    #[test]
+    fn conjuncts_join_with_zwj() {
+        assert_eq!(transliterate("kra"), "ක්\u{200D}ර");
+        assert_eq!(transliterate("kya"), "ක්\u{200D}ය");
+        assert_eq!(transliterate("r+ka"), "ර්\u{200D}ක");
+    }
+
+    #[test]
+    fn separator_keeps_letters_apart() {
+        assert_eq!(transliterate("kha"), "ඛ");
+        assert_eq!(transliterate("k:ha"), "ක්හ");
+    }
+
+    #[test]
+    fn l_with_a_vowel_is_not_vocalic_l() {
+        assert_eq!(transliterate("lu"), "ලු");
+        assert_eq!(transliterate("lunu"), "ලුනු");
+        assert_eq!(transliterate("lRu"), "ඏ");
+    }
+
+    #[test]
+    fn unknown_input_passes_through() {
+        assert_eq!(transliterate("ka 1"), "ක 1");
+    }
+}
@@ -0,0 +1,85 @@
+//! Every letter in the Sinhala Unicode block, with its category.
+
+use crate::{deck::Deck, pair::Pair};
+
+const SINHALA_BLOCK: std::ops::RangeInclusive<char> = '\u{0D80}'..='\u{0DFF}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LetterCategory {
+    Vowel,
+    Consonant,
+    /// Consonants with a short nasal before them, like ඟ (n̆ga).
+    Prenasalized,
+    /// Candrabindu, anusvara and visarga.
+    Sign,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Letter {
+    pub sinhala: char,
+    pub category: LetterCategory,
+}
+
+impl Letter {
+    pub fn pair(&self) -> Pair {
+        Pair::new(self.sinhala.into())
+    }
+
+    pub fn is_consonant(&self) -> bool {
+        matches!(
+            self.category,
+            LetterCategory::Consonant | LetterCategory::Prenasalized
+        )
+    }
+}
+
+/// All letters in code point order, which is also the traditional alphabet order.
+/// Vowel signs and the hal kirīma aren't letters on their own and are left out.
+pub fn letters() -> impl Iterator<Item = Letter> {
+    SINHALA_BLOCK.filter_map(|sinhala| {
+        Some(Letter {
+            sinhala,
+            category: category(sinhala)?,
+        })
+    })
+}
+
+pub fn letter(sinhala: char) -> Option<Letter> {
+    letters().find(|letter| letter.sinhala == sinhala)
+}
+
+/// A deck of all letters in the given categories.
+pub fn deck(name: &str, categories: &[LetterCategory]) -> Deck {
+    Deck {
+        name: name.into(),
+        cards: letters()
+            .filter(|letter| categories.contains(&letter.category))
+            .map(|letter| letter.pair())
+            .collect(),
+    }
+}
+
+pub fn decks() -> Vec<Deck> {
+    use LetterCategory::*;
+
+    vec![
+        deck("All letters", &[Vowel, Consonant, Prenasalized, Sign]),
+        deck("Vowels", &[Vowel]),
+        deck("Consonants", &[Consonant]),
+        deck("Prenasalized", &[Prenasalized]),
+        deck("Signs", &[Sign]),
+    ]
+}
+
+fn category(c: char) -> Option<LetterCategory> {
+    Some(match c {
+        '\u{0D81}'..='\u{0D83}' => LetterCategory::Sign,
+        '\u{0D85}'..='\u{0D96}' => LetterCategory::Vowel,
+        'ඟ' | 'ඦ' | 'ඬ' | 'ඳ' | 'ඹ' => LetterCategory::Prenasalized,
+        '\u{0D9A}'..='\u{0DB1}'
+        | '\u{0DB3}'..='\u{0DBB}'
+        | '\u{0DBD}'
+        | '\u{0DC0}'..='\u{0DC6}' => LetterCategory::Consonant,
+        _ => return None,
+    })
+}
@@ -0,0 +1,96 @@
+use bevy::{input::mouse::MouseMotion, prelude::*, ui::UiSystem, window::PrimaryWindow};
+
+use crate::settings::Settings;
+
+/// The window size the UI's pixel values were designed for, in landscape.
+const REFERENCE_SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+const MIN_UI_SCALE: f32 = 0.4;
+const MAX_UI_SCALE: f32 = 2.0;
+
+/// Which way round the primary window is, updated whenever it's resized or rotated.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Orientation {
+    #[default]
+    Landscape,
+    Portrait,
+}
+
+impl Orientation {
+    fn of(size: Vec2) -> Self {
+        if size.y > size.x {
+            Self::Portrait
+        } else {
+            Self::Landscape
+        }
+    }
+
+    /// Scale the UI so the reference layout fits the window, rotating the reference size in
+    /// portrait so a phone held upright gets the same proportions as a landscape desktop.
+    fn ui_scale(self, size: Vec2) -> f32 {
+        let reference = match self {
+            Self::Landscape => REFERENCE_SIZE,
+            Self::Portrait => REFERENCE_SIZE.yx(),
+        };
+        (size / reference)
+            .min_element()
+            .clamp(MIN_UI_SCALE, MAX_UI_SCALE)
+    }
+}
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Orientation>()
+            .add_systems(PreUpdate, release_touch_hover.after(UiSystem::Focus))
+            .add_systems(Update, track_window);
+    }
+}
+
+fn track_window(
+    windows: Query<Ref<Window>, With<PrimaryWindow>>,
+    settings: Res<Settings>,
+    mut orientation: ResMut<Orientation>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if !window.is_changed() && !settings.is_changed() {
+        return;
+    }
+    let size = window.size();
+    if size.min_element() <= 0.0 {
+        return;
+    }
+    let new_orientation = Orientation::of(size);
+    orientation.set_if_neq(new_orientation);
+    let scale = new_orientation.ui_scale(size) * settings.ui_size;
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+/// A finger lifting off the screen leaves the browser's emulated cursor behind, which would keep
+/// the last touched button highlighted as hovered. Touch has no hover, so until the mouse moves
+/// again, treat hovered buttons as untouched.
+fn release_touch_hover(
+    touches: Res<Touches>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut touching: Local<bool>,
+    mut interactions: Query<&mut Interaction>,
+) {
+    if touches.iter_just_pressed().next().is_some() {
+        *touching = true;
+    } else if mouse_motion.read().next().is_some() {
+        *touching = false;
+    }
+    mouse_motion.clear();
+    if *touching {
+        for mut interaction in &mut interactions {
+            if *interaction == Interaction::Hovered {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use rand::{prelude::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::pair::Pair;
+
+pub const BOXES: usize = 5;
+
+/// Which Leitner box each card sits in. Cards that were never answered are in the first box.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Leitner {
+    boxes: HashMap<String, usize>,
+}
+
+impl Leitner {
+    /// The card's box, kept in range even if the saved progress says otherwise.
+    pub fn box_of(&self, pair: &Pair) -> usize {
+        self.boxes
+            .get(pair.id())
+            .copied()
+            .unwrap_or_default()
+            .min(BOXES - 1)
+    }
+
+    /// Moves a card up one box when answered correctly, and back to the first box otherwise.
+    pub fn review(&mut self, pair: &Pair, correct: bool) {
+        let next = if correct {
+            (self.box_of(pair) + 1).min(BOXES - 1)
+        } else {
+            0
+        };
+        self.boxes.insert(pair.id().to_owned(), next);
+    }
+
+    /// Draws a card, with each box twice as likely to be drawn from as the one above it.
+    pub fn next<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = &'a Pair>,
+        rng: &mut impl Rng,
+    ) -> Option<&'a Pair> {
+        candidates
+            .into_iter()
+            .collect::<Vec<_>>()
+            .choose_weighted(rng, |pair| 1 << (BOXES - 1 - self.box_of(pair)))
+            .ok()
+            .copied()
+    }
+
+    pub fn distribution<'a>(&self, cards: impl IntoIterator<Item = &'a Pair>) -> [usize; BOXES] {
+        let mut distribution = [0; BOXES];
+        for pair in cards {
+            distribution[self.box_of(pair)] += 1;
+        }
+        distribution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_answers_move_up_to_the_last_box() {
+        let card = Pair::new("ක".to_owned());
+        let mut leitner = Leitner::default();
+        assert_eq!(leitner.box_of(&card), 0);
+        for expected in [1, 2, 3, 4, 4] {
+            leitner.review(&card, true);
+            assert_eq!(leitner.box_of(&card), expected);
+        }
+    }
+
+    #[test]
+    fn misses_go_back_to_the_first_box() {
+        let card = Pair::new("ක".to_owned());
+        let mut leitner = Leitner::default();
+        leitner.review(&card, true);
+        leitner.review(&card, true);
+        leitner.review(&card, false);
+        assert_eq!(leitner.box_of(&card), 0);
+    }
+
+    #[test]
+    fn saved_boxes_out_of_range_are_clamped() {
+        let cards = [Pair::new("ක".to_owned()), Pair::new("ග".to_owned())];
+        let leitner: Leitner = ron::from_str(r#"{"ක": 9}"#).unwrap();
+        assert_eq!(leitner.box_of(&cards[0]), BOXES - 1);
+        assert_eq!(leitner.distribution(&cards), [1, 0, 0, 0, 1]);
+    }
+}
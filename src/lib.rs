@@ -0,0 +1,18 @@
+//! Sinhala script tools and the quiz rules, independent of the game's UI and shared by
+//! every front end.
+
+pub mod conjuncts;
+pub mod deck;
+pub mod distractors;
+pub mod engine;
+pub mod history;
+pub mod ime;
+pub mod inventory;
+pub mod leitner;
+pub mod matching;
+pub mod pair;
+pub mod persistence;
+pub mod preferences;
+pub mod scheduler;
+pub mod syllables;
+pub mod transliteration;
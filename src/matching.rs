@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+use crate::transliteration::SEPARATOR;
+
+/// Breve marking a prenasalized consonant, as in "n̆ga".
+const COMBINING_BREVE: char = '\u{0306}';
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Strictness {
+    /// Diacritics and case have to be typed exactly, so "na" does not match "ṇa", nor "ta"
+    /// the Harvard-Kyoto "Ta".
+    Strict,
+    /// Diacritics and case are ignored, so "na" matches both "na" and "ṇa".
+    #[default]
+    Lenient,
+}
+impl Strictness {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Strictness::Strict => "Strict",
+            Strictness::Lenient => "Lenient",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchResult {
+    Correct,
+    /// Only the diacritics are wrong, or a longer answer is off by a single letter.
+    Close,
+    Incorrect,
+}
+
+pub fn check(typed: &str, expected: &str, strictness: Strictness) -> MatchResult {
+    if normalize(typed, strictness) == normalize(expected, strictness) {
+        return MatchResult::Correct;
+    }
+
+    let typed = normalize(typed, Strictness::Lenient);
+    let expected = normalize(expected, Strictness::Lenient);
+    // A single letter is most of a short answer, so only forgive it in longer ones.
+    if typed == expected || (expected.len() > 2 && edit_distance(&typed, &expected) <= 1) {
+        MatchResult::Close
+    } else {
+        MatchResult::Incorrect
+    }
+}
+
+fn normalize(text: &str, strictness: Strictness) -> Vec<char> {
+    // Case tells letters apart in Harvard-Kyoto and ASCII, like "T" for ට and "t" for ත.
+    let text = match strictness {
+        Strictness::Strict => text.trim().to_owned(),
+        Strictness::Lenient => text.trim().to_lowercase(),
+    };
+    let mut normalized = Vec::new();
+    // Neither is ever required when typing.
+    for c in text
+        .chars()
+        .filter(|&c| c != COMBINING_BREVE && c != SEPARATOR)
+    {
+        match strictness {
+            Strictness::Strict => normalized.push(c),
+            Strictness::Lenient if is_combining_mark(c) => {}
+            Strictness::Lenient => match fold(c) {
+                Some(folded) => normalized.extend(folded.chars()),
+                None => normalized.push(c),
+            },
+        }
+    }
+    normalized
+}
+
+fn is_combining_mark(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+/// Strips the precomposed diacritics used by the romanizations, spelling out æ as "ae".
+fn fold(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ā' => "a",
+        'æ' | 'ǣ' => "ae",
+        'ī' => "i",
+        'ū' => "u",
+        'ē' => "e",
+        'ō' => "o",
+        'ṅ' | 'ñ' | 'ṇ' => "n",
+        'ṭ' => "t",
+        'ḍ' => "d",
+        'ś' | 'ṣ' => "s",
+        'ḷ' | 'ḹ' => "l",
+        'ṛ' | 'ṝ' => "r",
+        'ṁ' | 'ṃ' => "m",
+        'ḥ' => "h",
+        _ => return None,
+    })
+}
+
+/// Levenshtein distance between two strings of characters.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_requires_diacritics() {
+        assert_eq!(check("ṇa", "ṇa", Strictness::Strict), MatchResult::Correct);
+        assert_eq!(check("na", "ṇa", Strictness::Strict), MatchResult::Close);
+    }
+
+    #[test]
+    fn lenient_ignores_diacritics() {
+        assert_eq!(check("na", "ṇa", Strictness::Lenient), MatchResult::Correct);
+        assert_eq!(
+            check("kae", "kæ", Strictness::Lenient),
+            MatchResult::Correct
+        );
+        assert_eq!(
+            check(" Ka ", "ka", Strictness::Lenient),
+            MatchResult::Correct
+        );
+    }
+
+    #[test]
+    fn strict_requires_case() {
+        assert_eq!(check("Ta", "Ta", Strictness::Strict), MatchResult::Correct);
+        assert_eq!(check("ta", "Ta", Strictness::Strict), MatchResult::Close);
+        assert_eq!(check("nA", "NA", Strictness::Strict), MatchResult::Close);
+        assert_eq!(check("a", "A", Strictness::Strict), MatchResult::Close);
+        assert_eq!(check("ta", "Ta", Strictness::Lenient), MatchResult::Correct);
+    }
+
+    #[test]
+    fn breve_is_never_required() {
+        for strictness in [Strictness::Strict, Strictness::Lenient] {
+            assert_eq!(check("nga", "n̆ga", strictness), MatchResult::Correct);
+        }
+    }
+
+    #[test]
+    fn separator_is_never_required() {
+        for strictness in [Strictness::Strict, Strictness::Lenient] {
+            assert_eq!(check("kha", "k:ha", strictness), MatchResult::Correct);
+        }
+    }
+
+    #[test]
+    fn one_letter_off_is_close_only_in_longer_answers() {
+        assert_eq!(
+            check("mother", "mothr", Strictness::Lenient),
+            MatchResult::Close
+        );
+        assert_eq!(
+            check("ki", "ka", Strictness::Lenient),
+            MatchResult::Incorrect
+        );
+        assert_eq!(
+            check("father", "mother", Strictness::Lenient),
+            MatchResult::Incorrect
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::transliteration::{self, RomanizationScheme};
+
+/// One side of a card that can be asked for or answered with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Field {
+    Sinhala,
+    Romanization,
+    Ipa,
+    Gloss,
+}
+
+impl Field {
+    pub const ALL: [Field; 4] = [
+        Field::Sinhala,
+        Field::Romanization,
+        Field::Ipa,
+        Field::Gloss,
+    ];
+
+    /// Short example of the field, used to label quiz modes.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Field::Sinhala => "ක",
+            Field::Romanization => "ka",
+            Field::Ipa => "/ka/",
+            Field::Gloss => "en",
+        }
+    }
+
+    /// How a value of this field is shown on screen.
+    pub fn render(&self, value: &str) -> String {
+        match *self {
+            Field::Ipa => format!("/{value}/"),
+            Field::Sinhala | Field::Romanization | Field::Gloss => value.to_owned(),
+        }
+    }
+}
+
+/// Which field of a card is shown as the prompt, and which one is expected as the answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuizMode {
+    pub prompt_field: Field,
+    pub answer_field: Field,
+}
+
+impl Default for QuizMode {
+    fn default() -> Self {
+        Self {
+            prompt_field: Field::Sinhala,
+            answer_field: Field::Romanization,
+        }
+    }
+}
+
+impl QuizMode {
+    /// Every mode between two different fields that all the given cards have.
+    pub fn available(cards: &[Pair]) -> Vec<QuizMode> {
+        let fields = Field::ALL
+            .into_iter()
+            .filter(|&field| cards.iter().all(|card| card.has(field)))
+            .collect::<Vec<_>>();
+        fields
+            .iter()
+            .flat_map(|&prompt_field| {
+                fields
+                    .iter()
+                    .filter(move |&&answer_field| answer_field != prompt_field)
+                    .map(move |&answer_field| QuizMode {
+                        prompt_field,
+                        answer_field,
+                    })
+            })
+            .collect()
+    }
+
+    pub fn supported_by(&self, cards: &[Pair]) -> bool {
+        cards
+            .iter()
+            .all(|card| card.has(self.prompt_field) && card.has(self.answer_field))
+    }
+
+    pub fn label(&self) -> String {
+        format!(
+            "{} -> {}",
+            self.prompt_field.label(),
+            self.answer_field.label()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PartOfSpeech {
+    Noun,
+    Pronoun,
+    Verb,
+    Adjective,
+    Adverb,
+    Particle,
+    Phrase,
+}
+
+impl PartOfSpeech {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            PartOfSpeech::Noun => "noun",
+            PartOfSpeech::Pronoun => "pronoun",
+            PartOfSpeech::Verb => "verb",
+            PartOfSpeech::Adjective => "adjective",
+            PartOfSpeech::Adverb => "adverb",
+            PartOfSpeech::Particle => "particle",
+            PartOfSpeech::Phrase => "phrase",
+        }
+    }
+}
+
+/// A card: a piece of Sinhala script and, for words, its pronunciation and meaning.
+/// The romanization is generated from the script under the chosen scheme.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub struct Pair {
+    sinhala: String,
+    #[serde(default)]
+    ipa: Option<String>,
+    /// English meaning, for words.
+    #[serde(default)]
+    gloss: Option<String>,
+    #[serde(default)]
+    part_of_speech: Option<PartOfSpeech>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl Pair {
+    pub fn new(sinhala: String) -> Self {
+        Self {
+            sinhala,
+            ..Default::default()
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.sinhala
+    }
+
+    pub fn has(&self, field: Field) -> bool {
+        match field {
+            Field::Sinhala | Field::Romanization => true,
+            Field::Ipa => self.ipa.is_some(),
+            Field::Gloss => self.gloss.is_some(),
+        }
+    }
+
+    pub fn field(&self, field: Field, scheme: RomanizationScheme) -> Option<Cow<'_, str>> {
+        match field {
+            Field::Sinhala => Some(self.sinhala.as_str().into()),
+            Field::Romanization => Some(transliteration::romanize(&self.sinhala, scheme).into()),
+            Field::Ipa => self.ipa.as_deref().map(Cow::from),
+            Field::Gloss => self.gloss.as_deref().map(Cow::from),
+        }
+    }
+
+    /// Part of speech and tags, like "noun, family", to tell apart words with similar meanings.
+    pub fn hint(&self) -> String {
+        self.part_of_speech
+            .iter()
+            .map(|part_of_speech| part_of_speech.label())
+            .chain(self.tags.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn question(&self, quiz_mode: QuizMode, scheme: RomanizationScheme) -> String {
+        let field = quiz_mode.prompt_field;
+        field.render(&self.field(field, scheme).unwrap_or_default())
+    }
+
+    pub fn answer(&self, quiz_mode: QuizMode, scheme: RomanizationScheme) -> String {
+        let field = quiz_mode.answer_field;
+        field.render(&self.field(field, scheme).unwrap_or_default())
+    }
+}
@@ -0,0 +1,361 @@
+//! Settings, progress and the answer history saved between sessions, in the platform's data
+//! directory or in the browser's localStorage.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    engine::{ActiveSetSize, AnswerMode, ChoiceCount, QuizEngine, SchedulingMode, SessionLength},
+    history::{self, Attempt, ExportFormat},
+    leitner::Leitner,
+    matching::Strictness,
+    pair::QuizMode,
+    preferences::Preferences,
+    scheduler::Scheduler,
+    transliteration::RomanizationScheme,
+};
+
+#[derive(Debug, Error)]
+pub enum PersistenceError {
+    #[error("Could not access saved data: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse saved progress: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize progress: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Could not read or write the answer history: {0}")]
+    History(#[from] serde_json::Error),
+    #[error("No data directory to save progress in")]
+    NoDataDir,
+    #[error("Could not access localStorage")]
+    Storage,
+    #[error("Could not start the download")]
+    Download,
+}
+
+/// Everything that survives between sessions.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    pub quiz_mode: QuizMode,
+    pub scheduling_mode: SchedulingMode,
+    pub answer_mode: AnswerMode,
+    pub strictness: Strictness,
+    pub romanization_scheme: RomanizationScheme,
+    pub session_length: SessionLength,
+    pub choice_count: ChoiceCount,
+    pub active_set_size: ActiveSetSize,
+    pub preferences: Preferences,
+    pub scheduler: Scheduler,
+    pub leitner: Leitner,
+    /// Ids of the cards in the active question set.
+    pub active_set: Vec<String>,
+}
+
+impl SaveData {
+    pub fn from_engine(engine: &QuizEngine, preferences: &Preferences) -> Self {
+        Self {
+            quiz_mode: engine.quiz_mode,
+            scheduling_mode: engine.scheduling_mode,
+            answer_mode: engine.answer_mode,
+            strictness: engine.strictness,
+            romanization_scheme: engine.romanization,
+            session_length: engine.session_length,
+            choice_count: engine.choice_count(),
+            active_set_size: engine.active_set_size(),
+            preferences: preferences.clone(),
+            scheduler: engine.scheduler().clone(),
+            leitner: engine.leitner().clone(),
+            active_set: engine
+                .active_set()
+                .iter()
+                .map(|card| card.id().to_owned())
+                .collect(),
+        }
+    }
+
+    /// An engine with the saved settings and progress. The active set can only be restored
+    /// once the deck has loaded. The [`Preferences`] are left for the front end to take.
+    pub fn into_engine(self) -> QuizEngine {
+        let mut engine = QuizEngine::new(self.scheduler, self.leitner);
+        engine.quiz_mode = self.quiz_mode;
+        engine.scheduling_mode = self.scheduling_mode;
+        engine.answer_mode = self.answer_mode;
+        engine.strictness = self.strictness;
+        engine.romanization = self.romanization_scheme;
+        engine.session_length = self.session_length;
+        engine.set_choice_count(self.choice_count);
+        engine.set_active_set_size(self.active_set_size);
+        engine
+    }
+}
+
+const PROGRESS: &str = "progress.ron";
+const HISTORY: &str = "history.jsonl";
+
+/// The saved progress, or `None` if nothing has been saved yet.
+pub fn load() -> Result<Option<SaveData>, PersistenceError> {
+    let Some(text) = storage::read(PROGRESS)? else {
+        return Ok(None);
+    };
+    Ok(Some(ron::from_str(&text)?))
+}
+
+pub fn save(data: &SaveData) -> Result<(), PersistenceError> {
+    storage::write(PROGRESS, &ron::to_string(data)?)
+}
+
+/// Adds an attempt to the end of the answer history. In the browser, the oldest attempts
+/// are dropped once the history outgrows a couple of megabytes.
+pub fn record(attempt: &Attempt) -> Result<(), PersistenceError> {
+    storage::append(HISTORY, &history::to_jsonl(std::slice::from_ref(attempt))?)
+}
+
+pub fn load_history() -> Result<Vec<Attempt>, PersistenceError> {
+    let text = storage::read(HISTORY)?.unwrap_or_default();
+    Ok(history::from_jsonl(&text)?)
+}
+
+/// Writes the answer history where it can be picked up for analysis: next to the saved
+/// progress natively, or as a download in the browser. Returns where it went.
+pub fn export_history(format: ExportFormat) -> Result<String, PersistenceError> {
+    let text = format.render(&load_history()?)?;
+    storage::export(&format!("history.{}", format.extension()), &text)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::{
+        fs::OpenOptions,
+        io::{ErrorKind, Write},
+        path::PathBuf,
+    };
+
+    use directories::ProjectDirs;
+
+    use super::PersistenceError;
+
+    /// The path of a file in the data directory, which is created if needed.
+    fn path(name: &str) -> Result<PathBuf, PersistenceError> {
+        let dirs =
+            ProjectDirs::from("", "", "sinhala_training").ok_or(PersistenceError::NoDataDir)?;
+        std::fs::create_dir_all(dirs.data_dir())?;
+        Ok(dirs.data_dir().join(name))
+    }
+
+    pub fn read(name: &str) -> Result<Option<String>, PersistenceError> {
+        match std::fs::read_to_string(path(name)?) {
+            Ok(text) => Ok(Some(text)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn write(name: &str, text: &str) -> Result<(), PersistenceError> {
+        std::fs::write(path(name)?, text)?;
+        Ok(())
+    }
+
+    pub fn append(name: &str, text: &str) -> Result<(), PersistenceError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path(name)?)?
+            .write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn export(name: &str, text: &str) -> Result<String, PersistenceError> {
+        let path = path(name)?;
+        std::fs::write(&path, text)?;
+        Ok(path.display().to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use wasm_bindgen::{JsCast, JsValue};
+    use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Storage, Url};
+
+    use super::PersistenceError;
+
+    /// Appended files are split over keys of at most this many bytes, so that adding to one
+    /// doesn't rewrite all of it.
+    const CHUNK_LEN: usize = 64 * 1024;
+    /// How many chunks of an appended file are kept before the oldest is dropped, staying well
+    /// within the few megabytes browsers allow a site.
+    const MAX_CHUNKS: usize = 32;
+
+    /// The localStorage key of a file, named after the file so both platforms agree.
+    fn key(name: &str) -> String {
+        format!(
+            "sinhala_training.{}",
+            name.split('.').next().unwrap_or(name)
+        )
+    }
+
+    /// The key of one chunk of a file. The first is the file's own key, so files that were
+    /// never appended to are a single chunk.
+    fn chunk_key(name: &str, index: usize) -> String {
+        match index {
+            0 => key(name),
+            _ => format!("{}.{index}", key(name)),
+        }
+    }
+
+    /// The key holding the first and last chunk of a file that has been appended to.
+    fn chunks_key(name: &str) -> String {
+        format!("{}.chunks", key(name))
+    }
+
+    fn local_storage() -> Result<Storage, PersistenceError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok()?)
+            .ok_or(PersistenceError::Storage)
+    }
+
+    fn get(storage: &Storage, key: &str) -> Result<Option<String>, PersistenceError> {
+        storage.get_item(key).map_err(|_| PersistenceError::Storage)
+    }
+
+    fn set(storage: &Storage, key: &str, value: &str) -> Result<(), PersistenceError> {
+        storage
+            .set_item(key, value)
+            .map_err(|_| PersistenceError::Storage)
+    }
+
+    fn remove(storage: &Storage, key: &str) -> Result<(), PersistenceError> {
+        storage
+            .remove_item(key)
+            .map_err(|_| PersistenceError::Storage)
+    }
+
+    /// The indices of the first and last chunk of a file.
+    fn chunks(storage: &Storage, name: &str) -> Result<(usize, usize), PersistenceError> {
+        let chunks = get(storage, &chunks_key(name))?.and_then(|chunks| {
+            let (first, last) = chunks.split_once(' ')?;
+            Some((first.parse().ok()?, last.parse().ok()?))
+        });
+        Ok(chunks.unwrap_or((0, 0)))
+    }
+
+    pub fn read(name: &str) -> Result<Option<String>, PersistenceError> {
+        let storage = local_storage()?;
+        let (first, last) = chunks(&storage, name)?;
+        let mut text = None::<String>;
+        for index in first..=last {
+            if let Some(chunk) = get(&storage, &chunk_key(name, index))? {
+                text.get_or_insert_with(String::new).push_str(&chunk);
+            }
+        }
+        Ok(text)
+    }
+
+    pub fn write(name: &str, text: &str) -> Result<(), PersistenceError> {
+        let storage = local_storage()?;
+        let (first, last) = chunks(&storage, name)?;
+        for index in first..=last {
+            remove(&storage, &chunk_key(name, index))?;
+        }
+        remove(&storage, &chunks_key(name))?;
+        set(&storage, &key(name), text)
+    }
+
+    /// Adds to the last chunk of the file, starting a new one when it's full and dropping the
+    /// oldest once there are [`MAX_CHUNKS`].
+    pub fn append(name: &str, text: &str) -> Result<(), PersistenceError> {
+        let storage = local_storage()?;
+        let (mut first, mut last) = chunks(&storage, name)?;
+        let mut chunk = get(&storage, &chunk_key(name, last))?.unwrap_or_default();
+        if !chunk.is_empty() && chunk.len() + text.len() > CHUNK_LEN {
+            chunk.clear();
+            last += 1;
+            if last - first >= MAX_CHUNKS {
+                remove(&storage, &chunk_key(name, first))?;
+                first += 1;
+            }
+        }
+        chunk.push_str(text);
+        set(&storage, &chunk_key(name, last), &chunk)?;
+        set(&storage, &chunks_key(name), &format!("{first} {last}"))
+    }
+
+    /// Offers the text as a file download.
+    pub fn export(name: &str, text: &str) -> Result<String, PersistenceError> {
+        let parts = js_sys::Array::of1(&JsValue::from_str(text));
+        let properties = BlobPropertyBag::new();
+        properties.set_type("text/plain");
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &properties)
+            .map_err(|_| PersistenceError::Download)?;
+        let url =
+            Url::create_object_url_with_blob(&blob).map_err(|_| PersistenceError::Download)?;
+
+        let anchor = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.create_element("a").ok())
+            .and_then(|element| element.dyn_into::<HtmlAnchorElement>().ok())
+            .ok_or(PersistenceError::Download)?;
+        anchor.set_href(&url);
+        anchor.set_download(name);
+        anchor.click();
+        Url::revoke_object_url(&url).map_err(|_| PersistenceError::Download)?;
+        Ok(name.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{deck, pair::Field};
+
+    #[test]
+    fn progress_round_trips_through_ron() {
+        let mut engine = QuizEngine::new(Scheduler::default(), Leitner::default());
+        engine.set_cards(deck::generated()[0].cards.clone());
+        engine.quiz_mode = QuizMode {
+            prompt_field: Field::Romanization,
+            answer_field: Field::Sinhala,
+        };
+        engine.scheduling_mode = SchedulingMode::Leitner;
+        engine.answer_mode = AnswerMode::Typed;
+        engine.strictness = Strictness::Strict;
+        engine.romanization = RomanizationScheme::HarvardKyoto;
+        engine.session_length = SessionLength::Minutes(5);
+        engine.set_choice_count(ChoiceCount::Options(9));
+        engine.set_active_set_size(ActiveSetSize(10));
+        engine.start_session(0.0);
+        let question = engine.next_question(&mut rand::thread_rng(), 0.0).unwrap();
+        engine.submit_answer("wrong", 1.0);
+        let preferences = Preferences {
+            deck: Some("Vowels".to_owned()),
+            ..Preferences::default()
+        };
+
+        let text = ron::to_string(&SaveData::from_engine(&engine, &preferences)).unwrap();
+        let saved = ron::from_str::<SaveData>(&text).unwrap();
+        assert_eq!(saved.preferences, preferences);
+        assert_eq!(saved.active_set.len(), 10);
+        let restored = saved.into_engine();
+        assert_eq!(restored.quiz_mode, engine.quiz_mode);
+        assert_eq!(restored.scheduling_mode, SchedulingMode::Leitner);
+        assert_eq!(restored.answer_mode, AnswerMode::Typed);
+        assert_eq!(restored.strictness, Strictness::Strict);
+        assert_eq!(restored.romanization, RomanizationScheme::HarvardKyoto);
+        assert_eq!(restored.session_length, SessionLength::Minutes(5));
+        assert_eq!(restored.choice_count(), ChoiceCount::Options(9));
+        assert_eq!(restored.active_set_size(), ActiveSetSize(10));
+        assert_eq!(
+            restored.scheduler().schedule(&question.pair),
+            engine.scheduler().schedule(&question.pair)
+        );
+        assert_eq!(restored.leitner().box_of(&question.pair), 0);
+    }
+
+    #[test]
+    fn missing_fields_load_as_defaults() {
+        let saved = ron::from_str::<SaveData>("(answer_mode: Typed)").unwrap();
+        assert_eq!(saved.answer_mode, AnswerMode::Typed);
+        assert_eq!(saved.session_length, SessionLength::default());
+        assert!(saved.active_set.is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+//! How the game looks and paces itself, saved alongside the quiz settings in
+//! [`SaveData`](crate::persistence::SaveData). Front ends read what applies to them.
+
+use serde::{Deserialize, Serialize};
+
+/// The colours of buttons and backgrounds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Grey,
+    Black,
+    Blue,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Grey, Theme::Black, Theme::Blue];
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Theme::Grey => "Grey",
+            Theme::Black => "Black",
+            Theme::Blue => "Blue",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// Seconds the result of an answer stays up before the next question.
+    pub feedback_delay: f32,
+    /// Scales text and everything around it, on top of fitting the window.
+    pub ui_size: f32,
+    pub theme: Theme,
+    /// The name of the deck last quizzed.
+    pub deck: Option<String>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            feedback_delay: 1.0,
+            ui_size: 1.0,
+            theme: Theme::default(),
+            deck: None,
+        }
+    }
+}
+
+impl Preferences {
+    /// The feedback delays to choose from in the settings.
+    pub const FEEDBACK_DELAYS: [f32; 4] = [0.5, 1.0, 2.0, 3.0];
+    /// The UI sizes to choose from in the settings.
+    pub const UI_SIZES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use rand::{seq::IteratorRandom, Rng};
+use serde::{Deserialize, Serialize};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::pair::Pair;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+/// How long a missed card waits before it is due again, in seconds.
+const RELEARN_DELAY: f64 = 60.0;
+const MIN_EASE: f64 = 1.3;
+/// Correct answers up to this many seconds count as fluent recall.
+const FLUENT_RESPONSE: f64 = 4.0;
+/// Correct answers taking longer than this many seconds count as hesitant recall.
+const HESITANT_RESPONSE: f64 = 12.0;
+/// How much the latest answer weighs in a card's average response time.
+const RESPONSE_TIME_WEIGHT: f64 = 0.3;
+
+/// Wall clock time in seconds since the unix epoch.
+pub fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// SM-2 scheduling state for a single card.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CardSchedule {
+    pub ease: f64,
+    /// Days until the next review after the last successful one.
+    pub interval: f64,
+    pub repetitions: u32,
+    /// Seconds since the unix epoch at which the card is due again.
+    pub due: f64,
+    /// Seconds taken to answer, averaged with more weight on recent answers.
+    #[serde(default)]
+    pub response_time: Option<f64>,
+}
+
+impl Default for CardSchedule {
+    fn default() -> Self {
+        Self {
+            ease: 2.5,
+            interval: 0.0,
+            repetitions: 0,
+            due: 0.0,
+            response_time: None,
+        }
+    }
+}
+
+impl CardSchedule {
+    /// Grades a review with an SM-2 quality from 0 (no recall) to 5 (perfect recall).
+    pub fn review(&mut self, quality: u8, now: f64) {
+        let quality = quality.min(5);
+        if quality < 3 {
+            self.repetitions = 0;
+            self.interval = 0.0;
+            self.due = now + RELEARN_DELAY;
+        } else {
+            self.interval = match self.repetitions {
+                0 => 1.0,
+                1 => 6.0,
+                _ => (self.interval * self.ease).round(),
+            };
+            self.repetitions += 1;
+            self.due = now + self.interval * SECONDS_PER_DAY;
+        }
+
+        let miss = f64::from(5 - quality);
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Scheduler {
+    cards: HashMap<String, CardSchedule>,
+}
+
+impl Scheduler {
+    pub fn schedule(&self, pair: &Pair) -> CardSchedule {
+        self.cards.get(pair.id()).copied().unwrap_or_default()
+    }
+
+    /// Grades an answer taking `response_time` seconds. Correct answers count as stronger
+    /// recall the faster they came, so slow ones come back sooner.
+    pub fn review(&mut self, pair: &Pair, correct: bool, response_time: f64, now: f64) {
+        let quality = if !correct {
+            1
+        } else if response_time <= FLUENT_RESPONSE {
+            5
+        } else if response_time <= HESITANT_RESPONSE {
+            4
+        } else {
+            3
+        };
+        let schedule = self.cards.entry(pair.id().to_owned()).or_default();
+        schedule.review(quality, now);
+        schedule.response_time = Some(match schedule.response_time {
+            Some(average) => average + RESPONSE_TIME_WEIGHT * (response_time - average),
+            None => response_time,
+        });
+    }
+
+    /// Picks the most overdue card, or the one due soonest when nothing is due yet.
+    /// Ties are broken at random, so cards that were never reviewed come up shuffled.
+    pub fn next<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = &'a Pair>,
+        rng: &mut impl Rng,
+    ) -> Option<&'a Pair> {
+        let candidates = candidates
+            .into_iter()
+            .map(|pair| (self.schedule(pair).due, pair))
+            .collect::<Vec<_>>();
+        let earliest = candidates
+            .iter()
+            .map(|&(due, _)| due)
+            .min_by(f64::total_cmp)?;
+
+        candidates
+            .into_iter()
+            .filter(|&(due, _)| due == earliest)
+            .map(|(_, pair)| pair)
+            .choose(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn intervals_grow_with_each_recall() {
+        let mut schedule = CardSchedule::default();
+        schedule.review(5, 0.0);
+        assert_eq!((schedule.repetitions, schedule.interval), (1, 1.0));
+        assert_close(schedule.due, SECONDS_PER_DAY);
+        schedule.review(5, 0.0);
+        assert_eq!(schedule.interval, 6.0);
+        // 6 days times the ease of 2.7 earned so far.
+        schedule.review(5, 0.0);
+        assert_eq!(schedule.interval, 16.0);
+        assert_close(schedule.ease, 2.8);
+    }
+
+    #[test]
+    fn misses_start_over_soon() {
+        let mut schedule = CardSchedule::default();
+        schedule.review(5, 0.0);
+        schedule.review(5, 0.0);
+        schedule.review(1, 100.0);
+        assert_eq!((schedule.repetitions, schedule.interval), (0, 0.0));
+        assert_close(schedule.due, 100.0 + RELEARN_DELAY);
+        assert_close(schedule.ease, 2.7 - 0.54);
+    }
+
+    #[test]
+    fn ease_follows_quality_down_to_its_minimum() {
+        for (quality, ease) in [(5, 2.6), (4, 2.5), (3, 2.36), (0, 1.7)] {
+            let mut schedule = CardSchedule::default();
+            schedule.review(quality, 0.0);
+            assert_close(schedule.ease, ease);
+        }
+        let mut schedule = CardSchedule::default();
+        for _ in 0..5 {
+            schedule.review(0, 0.0);
+        }
+        assert_close(schedule.ease, MIN_EASE);
+    }
+
+    #[test]
+    fn next_picks_the_most_overdue_card() {
+        let cards = [Pair::new("ක".to_owned()), Pair::new("ග".to_owned())];
+        let mut scheduler = Scheduler::default();
+        let mut rng = rand::thread_rng();
+        scheduler.review(&cards[0], true, 1.0, 0.0);
+        assert_eq!(scheduler.next(&cards, &mut rng), Some(&cards[1]));
+        scheduler.review(&cards[1], true, 1.0, 10.0);
+        assert_eq!(scheduler.next(&cards, &mut rng), Some(&cards[0]));
+        assert_eq!(scheduler.next([], &mut rng), None);
+    }
+
+    #[test]
+    fn slower_answers_count_as_weaker_recall() {
+        let card = Pair::new("ක".to_owned());
+        for (response_time, ease) in [(2.0, 2.6), (8.0, 2.5), (20.0, 2.36)] {
+            let mut scheduler = Scheduler::default();
+            scheduler.review(&card, true, response_time, 0.0);
+            assert_close(scheduler.schedule(&card).ease, ease);
+        }
+    }
+
+    #[test]
+    fn response_time_averages_toward_recent_answers() {
+        let card = Pair::new("ක".to_owned());
+        let mut scheduler = Scheduler::default();
+        scheduler.review(&card, true, 10.0, 0.0);
+        assert_eq!(scheduler.schedule(&card).response_time, Some(10.0));
+        scheduler.review(&card, false, 20.0, 0.0);
+        assert_close(scheduler.schedule(&card).response_time.unwrap(), 13.0);
+    }
+}
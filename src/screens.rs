@@ -0,0 +1,579 @@
+//! The screens around the quiz: the main menu, deck selection, the results of a session and
+//! the settings. The quiz screen itself lives in [`crate::ui`].
+
+use bevy::prelude::*;
+use sinhala_training::{
+    engine::{next_preset, ActiveSetSize, AnswerMode, ChoiceCount, SchedulingMode, SessionLength},
+    history::ExportFormat,
+    matching::Strictness,
+    persistence,
+    preferences::{Preferences, Theme},
+    transliteration::RomanizationScheme,
+};
+
+use crate::{
+    deck_asset::{ActiveDeck, AvailableDecks, DeckAsset},
+    settings::{Panel, Settings},
+    ui::{field_font, NextSession, Quiz},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, States)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    DeckSelect,
+    Quiz,
+    Results,
+    Settings,
+}
+
+/// A button on one of the menu screens, highlighted on hover.
+#[derive(Debug, Component)]
+struct MenuButton;
+
+#[derive(Debug, Component)]
+enum MainMenuButton {
+    Start,
+    Decks,
+    Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    Quit,
+}
+
+/// Selects the deck with this index in [`AvailableDecks`].
+#[derive(Debug, Component)]
+struct DeckButton(usize);
+
+#[derive(Debug, Component)]
+enum ResultsButton {
+    RetryMissed,
+    NewSession,
+    Menu,
+}
+
+#[derive(Debug, Component)]
+enum SettingsButton {
+    SwitchSessionLength,
+    SwitchChoiceCount,
+    SwitchScheduling,
+    SwitchAnswerMode,
+    SwitchStrictness,
+    SwitchRomanization,
+    SwitchActiveSetSize,
+    SwitchFeedbackDelay,
+    SwitchUiSize,
+    SwitchTheme,
+}
+
+/// Writes out the answer history in this format.
+#[derive(Debug, Component)]
+struct ExportButton(ExportFormat);
+
+/// Goes back to the main menu.
+#[derive(Debug, Component)]
+struct BackButton;
+
+pub struct ScreensPlugin;
+
+impl Plugin for ScreensPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .enable_state_scoped_entities::<AppState>()
+            .add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
+            .add_systems(OnEnter(AppState::DeckSelect), spawn_deck_select)
+            .add_systems(OnEnter(AppState::Results), spawn_results)
+            .add_systems(OnEnter(AppState::Settings), spawn_settings)
+            .add_systems(
+                Update,
+                (
+                    highlight_menu_buttons,
+                    back_button_system,
+                    main_menu_system.run_if(in_state(AppState::MainMenu)),
+                    deck_select_system.run_if(in_state(AppState::DeckSelect)),
+                    results_system.run_if(in_state(AppState::Results)),
+                    (settings_system, export_system).run_if(in_state(AppState::Settings)),
+                ),
+            );
+    }
+}
+
+fn spawn_screen(commands: &mut Commands, state: AppState) -> Entity {
+    commands
+        .spawn((
+            StateScoped(state),
+            Panel,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .id()
+}
+
+fn spawn_title(commands: &mut ChildBuilder, text: impl Into<String>, asset_server: &AssetServer) {
+    commands.spawn(
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font: asset_server
+                    .load("fonts/Noto_Sans_Sinhala/NotoSansSinhala-VariableFont_wdth,wght.ttf"),
+                font_size: 75.0,
+                ..default()
+            },
+        )
+        .with_text_justify(JustifyText::Center),
+    );
+}
+
+fn spawn_label(commands: &mut ChildBuilder, text: impl Into<String>, asset_server: &AssetServer) {
+    commands.spawn(
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font: asset_server.load("fonts/Noto_Serif/NotoSerif-VariableFont_wdth,wght.ttf"),
+                font_size: 30.0,
+                color: Color::srgb(0.8, 0.8, 0.8),
+                ..default()
+            },
+        )
+        .with_text_justify(JustifyText::Center),
+    );
+}
+
+fn spawn_button(
+    commands: &mut ChildBuilder,
+    button: impl Component,
+    text: impl Into<String>,
+    asset_server: &AssetServer,
+) {
+    commands
+        .spawn((
+            button,
+            MenuButton,
+            ButtonBundle {
+                style: Style {
+                    min_width: Val::Px(300.0),
+                    border: UiRect::all(Val::Px(5.0)),
+                    padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                border_color: BorderColor(Color::BLACK),
+                ..default()
+            },
+        ))
+        .with_children(|commands| {
+            commands.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server
+                        .load("fonts/Noto_Sans_Sinhala/NotoSansSinhala-VariableFont_wdth,wght.ttf"),
+                    font_size: 40.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Colours menu buttons by their interaction, including new ones.
+fn highlight_menu_buttons(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor, &mut BorderColor),
+        (Changed<Interaction>, With<MenuButton>),
+    >,
+    settings: Res<Settings>,
+) {
+    let palette = settings.palette();
+    for (interaction, mut color, mut border_color) in &mut buttons {
+        let (background, border) = match *interaction {
+            Interaction::Pressed => (palette.pressed_button, Color::WHITE),
+            Interaction::Hovered => (palette.hovered_button, Color::WHITE),
+            Interaction::None => (palette.button, Color::BLACK),
+        };
+        *color = background.into();
+        border_color.0 = border;
+    }
+}
+
+fn back_button_system(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if buttons
+        .iter()
+        .any(|&interaction| interaction == Interaction::Pressed)
+    {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+fn deck_name(decks: &Assets<DeckAsset>, deck: &Handle<DeckAsset>) -> String {
+    decks
+        .get(deck)
+        .map(|deck| deck.name.clone())
+        .or_else(|| deck.path().map(ToString::to_string))
+        .unwrap_or_default()
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    active_deck: Res<ActiveDeck>,
+    decks: Res<Assets<DeckAsset>>,
+) {
+    let screen = spawn_screen(&mut commands, AppState::MainMenu);
+    commands.entity(screen).with_children(|commands| {
+        spawn_title(commands, "සිංහල", &asset_server);
+        spawn_label(commands, deck_name(&decks, &active_deck), &asset_server);
+        spawn_button(commands, MainMenuButton::Start, "Start", &asset_server);
+        spawn_button(commands, MainMenuButton::Decks, "Decks", &asset_server);
+        spawn_button(
+            commands,
+            MainMenuButton::Settings,
+            "Settings",
+            &asset_server,
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        spawn_button(commands, MainMenuButton::Quit, "Quit", &asset_server);
+    });
+}
+
+fn main_menu_system(
+    buttons: Query<(&Interaction, &MainMenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MainMenuButton::Start => next_state.set(AppState::Quiz),
+            MainMenuButton::Decks => next_state.set(AppState::DeckSelect),
+            MainMenuButton::Settings => next_state.set(AppState::Settings),
+            #[cfg(not(target_arch = "wasm32"))]
+            MainMenuButton::Quit => {
+                exit.send(AppExit::Success);
+            }
+        }
+    }
+}
+
+fn spawn_deck_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    available_decks: Res<AvailableDecks>,
+    decks: Res<Assets<DeckAsset>>,
+) {
+    let screen = spawn_screen(&mut commands, AppState::DeckSelect);
+    commands.entity(screen).with_children(|commands| {
+        spawn_title(commands, "Decks", &asset_server);
+        for (index, deck) in available_decks.iter().enumerate() {
+            spawn_button(
+                commands,
+                DeckButton(index),
+                deck_name(&decks, deck),
+                &asset_server,
+            );
+        }
+        spawn_button(commands, BackButton, "Back", &asset_server);
+    });
+}
+
+fn deck_select_system(
+    buttons: Query<(&Interaction, &DeckButton), Changed<Interaction>>,
+    available_decks: Res<AvailableDecks>,
+    mut active_deck: ResMut<ActiveDeck>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, &DeckButton(index)) in &buttons {
+        if *interaction == Interaction::Pressed {
+            active_deck.0 = available_decks[index].clone();
+            next_state.set(AppState::Quiz);
+        }
+    }
+}
+
+/// How many missed cards the results list before summing up the rest.
+const MISSED_SHOWN: usize = 10;
+
+fn spawn_results(mut commands: Commands, asset_server: Res<AssetServer>, quiz: Res<Quiz>) {
+    let stats = quiz.stats();
+    let missed = &quiz.session().missed;
+    let prompt_font = field_font(quiz.quiz_mode.prompt_field, &asset_server);
+    let answer_font = field_font(quiz.quiz_mode.answer_field, &asset_server);
+    let boxes = stats
+        .leitner_boxes
+        .iter()
+        .enumerate()
+        .map(|(i, count)| format!("{}: {count}", i + 1))
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    let screen = spawn_screen(&mut commands, AppState::Results);
+    commands.entity(screen).with_children(|commands| {
+        spawn_title(commands, "Results", &asset_server);
+        spawn_label(
+            commands,
+            format!(
+                "{} of {} correct ({:.0}%)",
+                stats.correct,
+                stats.answered,
+                100.0 * stats.accuracy().unwrap_or_default()
+            ),
+            &asset_server,
+        );
+        if let Some(response_time) = stats.average_response_time() {
+            spawn_label(
+                commands,
+                format!("{response_time:.1} s per answer"),
+                &asset_server,
+            );
+        }
+        spawn_label(commands, format!("Leitner boxes  {boxes}"), &asset_server);
+
+        if !missed.is_empty() {
+            spawn_label(commands, "Missed", &asset_server);
+        }
+        for pair in missed.iter().take(MISSED_SHOWN) {
+            let style = |font: &Handle<Font>| TextStyle {
+                font: font.clone(),
+                font_size: 30.0,
+                ..default()
+            };
+            commands.spawn(TextBundle::from_sections([
+                TextSection::new(
+                    pair.question(quiz.quiz_mode, quiz.romanization),
+                    style(&prompt_font),
+                ),
+                TextSection::new("  ", style(&answer_font)),
+                TextSection::new(
+                    pair.answer(quiz.quiz_mode, quiz.romanization),
+                    style(&answer_font),
+                ),
+            ]));
+        }
+        if missed.len() > MISSED_SHOWN {
+            spawn_label(
+                commands,
+                format!("and {} more", missed.len() - MISSED_SHOWN),
+                &asset_server,
+            );
+        }
+
+        if !missed.is_empty() {
+            spawn_button(
+                commands,
+                ResultsButton::RetryMissed,
+                "Retry misses",
+                &asset_server,
+            );
+        }
+        spawn_button(
+            commands,
+            ResultsButton::NewSession,
+            "New session",
+            &asset_server,
+        );
+        spawn_button(commands, ResultsButton::Menu, "Menu", &asset_server);
+    });
+}
+
+fn results_system(
+    buttons: Query<(&Interaction, &ResultsButton), Changed<Interaction>>,
+    mut next_session: ResMut<NextSession>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ResultsButton::RetryMissed => {
+                *next_session = NextSession::RetryMissed;
+                next_state.set(AppState::Quiz);
+            }
+            ResultsButton::NewSession => {
+                *next_session = NextSession::New;
+                next_state.set(AppState::Quiz);
+            }
+            ResultsButton::Menu => next_state.set(AppState::MainMenu),
+        }
+    }
+}
+
+impl SettingsButton {
+    const ALL: [SettingsButton; 10] = [
+        SettingsButton::SwitchSessionLength,
+        SettingsButton::SwitchChoiceCount,
+        SettingsButton::SwitchActiveSetSize,
+        SettingsButton::SwitchScheduling,
+        SettingsButton::SwitchAnswerMode,
+        SettingsButton::SwitchStrictness,
+        SettingsButton::SwitchRomanization,
+        SettingsButton::SwitchFeedbackDelay,
+        SettingsButton::SwitchUiSize,
+        SettingsButton::SwitchTheme,
+    ];
+
+    fn text(&self, quiz: &Quiz, settings: &Settings) -> String {
+        match self {
+            SettingsButton::SwitchSessionLength => {
+                format!("Sessions: {}", quiz.session_length.label())
+            }
+            SettingsButton::SwitchChoiceCount => {
+                format!("Choices: {}", quiz.choice_count().label())
+            }
+            SettingsButton::SwitchScheduling => {
+                format!("Scheduling: {}", quiz.scheduling_mode.label())
+            }
+            SettingsButton::SwitchAnswerMode => format!("Answers: {}", quiz.answer_mode.label()),
+            SettingsButton::SwitchStrictness => {
+                format!("Typed answers: {}", quiz.strictness.label())
+            }
+            SettingsButton::SwitchRomanization => {
+                format!("Romanization: {}", quiz.romanization.label())
+            }
+            SettingsButton::SwitchActiveSetSize => {
+                format!("Set size: {}", quiz.active_set_size().label())
+            }
+            SettingsButton::SwitchFeedbackDelay => {
+                format!("Feedback: {} s", settings.feedback_delay)
+            }
+            SettingsButton::SwitchUiSize => format!("Size: {:.0}%", 100.0 * settings.ui_size),
+            SettingsButton::SwitchTheme => format!("Theme: {}", settings.theme.label()),
+        }
+    }
+}
+
+fn spawn_settings(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    quiz: Res<Quiz>,
+    settings: Res<Settings>,
+) {
+    let screen = spawn_screen(&mut commands, AppState::Settings);
+    commands.entity(screen).with_children(|commands| {
+        spawn_title(commands, "Settings", &asset_server);
+        // Two columns, so that all the settings fit on the screen.
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    display: Display::Grid,
+                    grid_template_columns: vec![RepeatedGridTrack::auto(2)],
+                    row_gap: Val::Px(10.0),
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|commands| {
+                for setting in SettingsButton::ALL {
+                    let text = setting.text(&quiz, &settings);
+                    spawn_button(commands, setting, text, &asset_server);
+                }
+            });
+        for format in ExportFormat::ALL {
+            spawn_button(
+                commands,
+                ExportButton(format),
+                format!("Export history ({})", format.label()),
+                &asset_server,
+            );
+        }
+        spawn_button(commands, BackButton, "Back", &asset_server);
+    });
+}
+
+fn settings_system(
+    buttons: Query<(&Interaction, &SettingsButton, &Children), Changed<Interaction>>,
+    mut text: Query<&mut Text>,
+    mut quiz: ResMut<Quiz>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, setting, children) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match setting {
+            SettingsButton::SwitchSessionLength => {
+                quiz.session_length = next_preset(&SessionLength::PRESETS, quiz.session_length);
+            }
+            SettingsButton::SwitchChoiceCount => {
+                let next = next_preset(&ChoiceCount::PRESETS, quiz.choice_count());
+                quiz.set_choice_count(next);
+            }
+            SettingsButton::SwitchActiveSetSize => {
+                let next = next_preset(&ActiveSetSize::PRESETS, quiz.active_set_size());
+                quiz.set_active_set_size(next);
+            }
+            SettingsButton::SwitchScheduling => {
+                quiz.scheduling_mode = match quiz.scheduling_mode {
+                    SchedulingMode::SpacedRepetition => SchedulingMode::Leitner,
+                    SchedulingMode::Leitner => SchedulingMode::SpacedRepetition,
+                };
+            }
+            SettingsButton::SwitchAnswerMode => {
+                quiz.answer_mode = match quiz.answer_mode {
+                    AnswerMode::MultipleChoice => AnswerMode::Typed,
+                    AnswerMode::Typed => AnswerMode::MultipleChoice,
+                };
+            }
+            SettingsButton::SwitchStrictness => {
+                quiz.strictness = match quiz.strictness {
+                    Strictness::Strict => Strictness::Lenient,
+                    Strictness::Lenient => Strictness::Strict,
+                };
+            }
+            SettingsButton::SwitchRomanization => {
+                quiz.romanization = next_preset(&RomanizationScheme::ALL, quiz.romanization);
+            }
+            SettingsButton::SwitchFeedbackDelay => {
+                settings.feedback_delay =
+                    next_preset(&Preferences::FEEDBACK_DELAYS, settings.feedback_delay);
+            }
+            SettingsButton::SwitchUiSize => {
+                settings.ui_size = next_preset(&Preferences::UI_SIZES, settings.ui_size);
+            }
+            SettingsButton::SwitchTheme => {
+                settings.theme = next_preset(&Theme::ALL, settings.theme);
+            }
+        }
+        if let Ok(mut text) = text.get_mut(children[0]) {
+            text.sections[0].value = setting.text(&quiz, &settings);
+        }
+    }
+}
+
+fn export_system(
+    buttons: Query<(&Interaction, &ExportButton, &Children), Changed<Interaction>>,
+    mut text: Query<&mut Text>,
+) {
+    for (interaction, &ExportButton(format), children) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let status = match persistence::export_history(format) {
+            Ok(location) => {
+                info!("Exported the answer history to {location}");
+                format!("Exported to {location}")
+            }
+            Err(err) => {
+                warn!("{err}");
+                "Export failed".to_owned()
+            }
+        };
+        if let Ok(mut text) = text.get_mut(children[0]) {
+            text.sections[0].value = status;
+        }
+    }
+}
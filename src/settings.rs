@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use sinhala_training::preferences::{Preferences, Theme};
+
+/// The saved [`Preferences`], changed on the settings screen and read by the systems that
+/// pace and draw the game.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct Settings(pub Preferences);
+
+/// A screen or bar background, coloured by the theme.
+#[derive(Debug, Component)]
+pub struct Panel;
+
+/// The colours of a [`Theme`].
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub button: Color,
+    pub hovered_button: Color,
+    pub pressed_button: Color,
+    pub panel: Color,
+    /// Behind everything, where no panel is.
+    pub background: Color,
+}
+
+impl Settings {
+    pub fn palette(&self) -> Palette {
+        match self.theme {
+            Theme::Grey => Palette {
+                button: Color::srgb(0.15, 0.15, 0.15),
+                hovered_button: Color::srgb(0.25, 0.25, 0.25),
+                pressed_button: Color::srgb(0.20, 0.20, 0.20),
+                panel: Color::srgb(0.20, 0.20, 0.20),
+                background: Color::srgb_u8(43, 44, 47),
+            },
+            Theme::Black => Palette {
+                button: Color::srgb(0.05, 0.05, 0.05),
+                hovered_button: Color::srgb(0.30, 0.30, 0.30),
+                pressed_button: Color::srgb(0.15, 0.15, 0.15),
+                panel: Color::BLACK,
+                background: Color::BLACK,
+            },
+            Theme::Blue => Palette {
+                button: Color::srgb(0.10, 0.15, 0.30),
+                hovered_button: Color::srgb(0.20, 0.28, 0.50),
+                pressed_button: Color::srgb(0.15, 0.22, 0.40),
+                panel: Color::srgb(0.12, 0.16, 0.26),
+                background: Color::srgb(0.08, 0.10, 0.18),
+            },
+        }
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_theme);
+    }
+}
+
+/// Colours new panels, and everything when the theme changes.
+fn apply_theme(
+    settings: Res<Settings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut panels: Query<(Ref<Panel>, &mut BackgroundColor), Without<Button>>,
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), With<Button>>,
+) {
+    let palette = settings.palette();
+    for (panel, mut color) in &mut panels {
+        if settings.is_changed() || panel.is_added() {
+            color.0 = palette.panel;
+        }
+    }
+    if !settings.is_changed() {
+        return;
+    }
+
+    if clear_color.0 != palette.background {
+        clear_color.0 = palette.background;
+    }
+    for (interaction, mut color) in &mut buttons {
+        color.0 = match interaction {
+            Interaction::Pressed => palette.pressed_button,
+            Interaction::Hovered => palette.hovered_button,
+            Interaction::None => palette.button,
+        };
+    }
+}
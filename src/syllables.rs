@@ -0,0 +1,48 @@
+//! Consonant + vowel sign (pili) syllables, generated instead of written out by hand.
+
+use crate::{
+    deck::Deck,
+    inventory,
+    pair::Pair,
+    transliteration::{self, RomanizationScheme},
+};
+
+/// Vowel signs. The first entry is the inherent "a", which has no sign, and the last is
+/// the hal kirīma, which removes it.
+const VOWEL_SIGNS: &[&str] = &[
+    "", "ා", "ැ", "ෑ", "ි", "ී", "ු", "ූ", "ෘ", "ෲ", "ෙ", "ේ", "ෛ", "ො", "ෝ", "ෞ", "්",
+];
+
+/// Every consonant combined with the given vowel sign.
+pub fn syllables(sign: &str) -> Vec<Pair> {
+    inventory::letters()
+        .filter(|letter| letter.is_consonant())
+        .map(|letter| Pair::new(format!("{}{sign}", letter.sinhala)))
+        .collect()
+}
+
+/// A deck for each vowel sign, followed by one with all of them together.
+pub fn decks() -> Vec<Deck> {
+    let mut decks = VOWEL_SIGNS
+        .iter()
+        .skip(1)
+        .map(|sign| {
+            let example = format!("ක{sign}");
+            Deck {
+                name: format!(
+                    "{example} ({})",
+                    transliteration::romanize(&example, RomanizationScheme::default())
+                ),
+                cards: syllables(sign),
+            }
+        })
+        .collect::<Vec<_>>();
+    decks.push(Deck {
+        name: "All syllables".into(),
+        cards: VOWEL_SIGNS
+            .iter()
+            .flat_map(|sign| syllables(sign))
+            .collect(),
+    });
+    decks
+}
@@ -0,0 +1,450 @@
+//! Conversion between Sinhala script and its romanizations, in both directions.
+//!
+//! Romanizing never loses anything but the ZWJ that picks a conjunct's written form, and
+//! reading a romanization back adds it again for rakāransaya and yansaya, so
+//! `to_sinhala(&romanize(text, scheme), scheme)` gives back `text` for anything but
+//! touching letters and repaya. Letters that would run together into another one, like ක්හ
+//! into "kha" for ඛ or අඅ into ASCII "aa" for ආ, are kept apart with a [`SEPARATOR`], as
+//! ISO 15919 does: "k:ha".
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+const HAL_KIRIMA: char = '\u{0DCA}';
+const ZWJ: char = '\u{200D}';
+const ZWNJ: char = '\u{200C}';
+const JOINER: char = '+';
+/// Keeps letters apart when reading back, without standing for anything itself.
+pub const SEPARATOR: char = ':';
+const INHERENT_VOWEL: &str = "a";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RomanizationScheme {
+    #[default]
+    Iso15919,
+    /// The Sri Lankan standard, which writes vocalic r and the anusvara with a dot below.
+    Sls1134,
+    /// Plain ASCII with capitals for long vowels and retroflex consonants.
+    HarvardKyoto,
+    /// The spelling the phonetic input takes, like "aa" for ā and "T" for ṭ.
+    Ascii,
+}
+
+impl RomanizationScheme {
+    pub const ALL: [RomanizationScheme; 4] = [
+        RomanizationScheme::Iso15919,
+        RomanizationScheme::Sls1134,
+        RomanizationScheme::HarvardKyoto,
+        RomanizationScheme::Ascii,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            RomanizationScheme::Iso15919 => "ISO 15919",
+            RomanizationScheme::Sls1134 => "SLS 1134",
+            RomanizationScheme::HarvardKyoto => "Harvard-Kyoto",
+            RomanizationScheme::Ascii => "ASCII",
+        }
+    }
+
+    fn column(&self) -> usize {
+        match *self {
+            RomanizationScheme::Iso15919 => 0,
+            RomanizationScheme::Sls1134 => 1,
+            RomanizationScheme::HarvardKyoto => 2,
+            RomanizationScheme::Ascii => 3,
+        }
+    }
+}
+
+/// Candrabindu, anusvara and visarga in each scheme, in the order of
+/// [`RomanizationScheme::ALL`].
+const SIGNS: &[(char, [&str; 4])] = &[
+    ('ඁ', ["m̐", "m̐", "~", "~"]),
+    ('ං', ["ṁ", "ṃ", "M", "x"]),
+    ('ඃ', ["ḥ", "ḥ", "H", "H"]),
+];
+
+/// Vowels as (independent letter, vowel sign), with no sign for the inherent "a". ASCII spells
+/// vocalic l "lRu" like the phonetic input.
+const VOWELS: &[(char, Option<char>, [&str; 4])] = &[
+    ('අ', None, ["a", "a", "a", "a"]),
+    ('ආ', Some('ා'), ["ā", "ā", "A", "aa"]),
+    ('ඇ', Some('ැ'), ["æ", "æ", "ae", "ae"]),
+    ('ඈ', Some('ෑ'), ["ǣ", "ǣ", "AE", "aae"]),
+    ('ඉ', Some('ි'), ["i", "i", "i", "i"]),
+    ('ඊ', Some('ී'), ["ī", "ī", "I", "ii"]),
+    ('උ', Some('ු'), ["u", "u", "u", "u"]),
+    ('ඌ', Some('ූ'), ["ū", "ū", "U", "uu"]),
+    ('ඍ', Some('ෘ'), ["r̥", "ṛ", "R", "R"]),
+    ('ඎ', Some('ෲ'), ["r̥̄", "ṝ", "RR", "RR"]),
+    ('ඏ', Some('ෟ'), ["l̥", "l̥", "lR", "lRu"]),
+    ('ඐ', Some('ෳ'), ["l̥̄", "l̥̄", "lRR", "lRuu"]),
+    ('එ', Some('ෙ'), ["e", "e", "e", "e"]),
+    ('ඒ', Some('ේ'), ["ē", "ē", "E", "ee"]),
+    ('ඓ', Some('ෛ'), ["ai", "ai", "ai", "ai"]),
+    ('ඔ', Some('ො'), ["o", "o", "o", "o"]),
+    ('ඕ', Some('ෝ'), ["ō", "ō", "O", "oo"]),
+    ('ඖ', Some('ෞ'), ["au", "au", "au", "au"]),
+];
+
+/// Consonants without their inherent vowel. ASCII spells ñ like Harvard-Kyoto, since "ny" is
+/// also න with yansaya.
+const CONSONANTS: &[(char, [&str; 4])] = &[
+    ('ක', ["k", "k", "k", "k"]),
+    ('ඛ', ["kh", "kh", "kh", "kh"]),
+    ('ග', ["g", "g", "g", "g"]),
+    ('ඝ', ["gh", "gh", "gh", "gh"]),
+    ('ඞ', ["ṅ", "ṅ", "G", "ng"]),
+    ('ඟ', ["n̆g", "n̆g", "~g", "^g"]),
+    ('ච', ["c", "c", "c", "c"]),
+    ('ඡ', ["ch", "ch", "ch", "ch"]),
+    ('ජ', ["j", "j", "j", "j"]),
+    ('ඣ', ["jh", "jh", "jh", "jh"]),
+    ('ඤ', ["ñ", "ñ", "J", "J"]),
+    ('ඥ', ["jñ", "jñ", "jJ", "jJ"]),
+    ('ඦ', ["n̆j", "n̆j", "~j", "^j"]),
+    ('ට', ["ṭ", "ṭ", "T", "T"]),
+    ('ඨ', ["ṭh", "ṭh", "Th", "Th"]),
+    ('ඩ', ["ḍ", "ḍ", "D", "D"]),
+    ('ඪ', ["ḍh", "ḍh", "Dh", "Dh"]),
+    ('ණ', ["ṇ", "ṇ", "N", "N"]),
+    ('ඬ', ["n̆ḍ", "n̆ḍ", "~D", "^D"]),
+    ('ත', ["t", "t", "t", "t"]),
+    ('ථ', ["th", "th", "th", "th"]),
+    ('ද', ["d", "d", "d", "d"]),
+    ('ධ', ["dh", "dh", "dh", "dh"]),
+    ('න', ["n", "n", "n", "n"]),
+    ('ඳ', ["n̆d", "n̆d", "~d", "^d"]),
+    ('ප', ["p", "p", "p", "p"]),
+    ('ඵ', ["ph", "ph", "ph", "ph"]),
+    ('බ', ["b", "b", "b", "b"]),
+    ('භ', ["bh", "bh", "bh", "bh"]),
+    ('ම', ["m", "m", "m", "m"]),
+    ('ඹ', ["m̆b", "m̆b", "~b", "^b"]),
+    ('ය', ["y", "y", "y", "y"]),
+    ('ර', ["r", "r", "r", "r"]),
+    ('ල', ["l", "l", "l", "l"]),
+    ('ව', ["v", "v", "v", "v"]),
+    ('ශ', ["ś", "ś", "z", "sh"]),
+    ('ෂ', ["ṣ", "ṣ", "S", "Sh"]),
+    ('ස', ["s", "s", "s", "s"]),
+    ('හ', ["h", "h", "h", "h"]),
+    ('ළ', ["ḷ", "ḷ", "L", "L"]),
+    ('ෆ', ["f", "f", "f", "f"]),
+];
+
+fn consonant(c: char, scheme: RomanizationScheme) -> Option<&'static str> {
+    CONSONANTS
+        .iter()
+        .find(|&&(sinhala, _)| sinhala == c)
+        .map(|(_, roman)| roman[scheme.column()])
+}
+
+fn independent_letter(c: char, scheme: RomanizationScheme) -> Option<&'static str> {
+    let roman = SIGNS
+        .iter()
+        .find(|&&(sign, _)| sign == c)
+        .map(|(_, roman)| roman)
+        .or_else(|| {
+            VOWELS
+                .iter()
+                .find(|&&(letter, _, _)| letter == c)
+                .map(|(_, _, roman)| roman)
+        })?;
+    Some(roman[scheme.column()])
+}
+
+fn vowel_sign(c: char, scheme: RomanizationScheme) -> Option<&'static str> {
+    VOWELS
+        .iter()
+        .find(|&&(_, sign, _)| sign == Some(c))
+        .map(|(_, _, roman)| roman[scheme.column()])
+}
+
+/// Romanizes Sinhala text. Consonants get the inherent vowel unless a vowel sign or hal
+/// kirīma follows, and anything that isn't Sinhala is passed through untouched.
+pub fn romanize(text: &str, scheme: RomanizationScheme) -> String {
+    let mut sounds = Vec::new();
+    let mut inherent_vowel = false;
+
+    for (i, c) in text.char_indices() {
+        if c == ZWJ || c == ZWNJ {
+            continue;
+        }
+        if let Some(vowel) = vowel_sign(c, scheme) {
+            sounds.push(vowel);
+            inherent_vowel = false;
+            continue;
+        }
+        if c == HAL_KIRIMA {
+            inherent_vowel = false;
+            continue;
+        }
+
+        if inherent_vowel {
+            sounds.push(INHERENT_VOWEL);
+        }
+        inherent_vowel = false;
+        if let Some(roman) = consonant(c, scheme) {
+            sounds.push(roman);
+            inherent_vowel = true;
+        } else if let Some(roman) = independent_letter(c, scheme) {
+            sounds.push(roman);
+        } else {
+            sounds.push(&text[i..i + c.len_utf8()]);
+        }
+    }
+    if inherent_vowel {
+        sounds.push(INHERENT_VOWEL);
+    }
+
+    // From the end, so each sound is checked against how the rest will be read.
+    let spelling = Spelling::new(&[scheme]);
+    let mut output = String::new();
+    for sound in sounds.into_iter().rev() {
+        let joined = format!("{sound}{output}");
+        let runs_on = spelling
+            .longest_match(&joined)
+            .is_some_and(|(typed, _)| typed.len() > sound.len());
+        if runs_on {
+            output.insert(0, SEPARATOR);
+        }
+        output.insert_str(0, sound);
+    }
+    output
+}
+
+/// Reads romanized text in the given scheme back into Sinhala script.
+pub fn to_sinhala(text: &str, scheme: RomanizationScheme) -> String {
+    Spelling::new(&[scheme]).to_sinhala(text)
+}
+
+/// Splits Sinhala text into the pieces a reader sees as one letter: a consonant with its
+/// vowel sign or hal kirīma, an independent vowel, or a conjunct joined with a ZWJ.
+///
+/// Extended grapheme clusters already keep vowel signs with their consonant, but end a
+/// cluster after a ZWJ, so those are joined with the letter that follows.
+pub fn clusters(text: &str) -> Vec<&str> {
+    let mut clusters = Vec::<&str>::new();
+    let mut start = None;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        let cluster_start = start.take().unwrap_or(offset);
+        if grapheme.ends_with(ZWJ) {
+            start = Some(cluster_start);
+        } else {
+            clusters.push(&text[cluster_start..offset + grapheme.len()]);
+        }
+    }
+    if let Some(start) = start {
+        clusters.push(&text[start..]);
+    }
+    clusters
+}
+
+/// Each of the text's [`clusters`] with its romanization.
+pub fn segment(text: &str, scheme: RomanizationScheme) -> Vec<(&str, String)> {
+    clusters(text)
+        .into_iter()
+        .map(|cluster| (cluster, romanize(cluster, scheme)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sound {
+    Consonant(char),
+    Vowel { letter: char, sign: Option<char> },
+    Sign(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// At the start, or after a vowel, sign or anything else that isn't a consonant.
+    Free,
+    /// After a consonant that hasn't had a vowel yet.
+    Consonant,
+    /// After a consonant and an explicit joiner.
+    Joined,
+}
+
+/// The ways of spelling each sound in one or more schemes, read back into Sinhala.
+///
+/// A consonant that isn't followed by a vowel gets a hal kirīma, and "r"/"y" after a bare
+/// consonant join as rakāransaya/yansaya ("kra" → ක්‍ර). A "+" joins any two consonants
+/// with a ZWJ ("r+ka" → ර්‍ක), and a [`SEPARATOR`] keeps letters apart ("k:ha" → ක්හ).
+#[derive(Debug, Clone)]
+pub struct Spelling {
+    entries: Vec<(&'static str, Sound)>,
+}
+
+impl Spelling {
+    /// Every spelling from all the given schemes.
+    pub fn new(schemes: &[RomanizationScheme]) -> Self {
+        let mut entries = Vec::new();
+        for scheme in schemes {
+            let column = scheme.column();
+            entries.extend(
+                CONSONANTS
+                    .iter()
+                    .map(|&(c, roman)| (roman[column], Sound::Consonant(c))),
+            );
+            entries.extend(
+                VOWELS
+                    .iter()
+                    .map(|&(letter, sign, roman)| (roman[column], Sound::Vowel { letter, sign })),
+            );
+            entries.extend(
+                SIGNS
+                    .iter()
+                    .map(|&(c, roman)| (roman[column], Sound::Sign(c))),
+            );
+        }
+        Self { entries }
+    }
+
+    /// Adds another way of spelling a consonant, like "w" for ව.
+    pub fn with_consonant(mut self, typed: &'static str, consonant: char) -> Self {
+        self.entries.push((typed, Sound::Consonant(consonant)));
+        self
+    }
+
+    fn longest_match(&self, input: &str) -> Option<(&'static str, Sound)> {
+        self.entries
+            .iter()
+            .filter(|(typed, _)| input.starts_with(typed))
+            .max_by_key(|(typed, _)| typed.len())
+            .copied()
+    }
+
+    /// Converts the text by greedy longest match. Anything that isn't spelled in the
+    /// schemes is passed through untouched.
+    pub fn to_sinhala(&self, input: &str) -> String {
+        let mut output = String::new();
+        let mut state = State::Free;
+        let mut rest = input;
+
+        while let Some(c) = rest.chars().next() {
+            match self.longest_match(rest) {
+                Some((typed, Sound::Consonant(consonant))) => {
+                    if state == State::Consonant {
+                        output.push(HAL_KIRIMA);
+                        if matches!(consonant, 'ර' | 'ය') {
+                            output.push(ZWJ);
+                        }
+                    }
+                    output.push(consonant);
+                    state = State::Consonant;
+                    rest = &rest[typed.len()..];
+                }
+                Some((typed, Sound::Vowel { letter, sign })) => {
+                    match state {
+                        State::Consonant => output.extend(sign),
+                        State::Joined | State::Free => output.push(letter),
+                    }
+                    state = State::Free;
+                    rest = &rest[typed.len()..];
+                }
+                None if c == SEPARATOR => {
+                    rest = &rest[c.len_utf8()..];
+                }
+                None if c == JOINER && state == State::Consonant => {
+                    output.push(HAL_KIRIMA);
+                    output.push(ZWJ);
+                    state = State::Joined;
+                    rest = &rest[c.len_utf8()..];
+                }
+                other => {
+                    if state == State::Consonant {
+                        output.push(HAL_KIRIMA);
+                    }
+                    match other {
+                        Some((typed, Sound::Sign(sign))) => {
+                            output.push(sign);
+                            rest = &rest[typed.len()..];
+                        }
+                        _ => {
+                            output.push(c);
+                            rest = &rest[c.len_utf8()..];
+                        }
+                    }
+                    state = State::Free;
+                }
+            }
+        }
+
+        if state == State::Consonant {
+            output.push(HAL_KIRIMA);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+    use crate::deck;
+
+    /// The text with the ZWJs that reading back can't restore taken out: all but those of
+    /// rakāransaya and yansaya.
+    fn without_touching_zwj(text: &str) -> String {
+        let chars = text.chars().collect::<Vec<_>>();
+        chars
+            .iter()
+            .enumerate()
+            .filter(|&(i, &c)| c != ZWJ || matches!(chars.get(i + 1), Some('ර' | 'ය')))
+            .map(|(_, &c)| c)
+            .collect()
+    }
+
+    fn assert_round_trips(text: &str) {
+        for scheme in RomanizationScheme::ALL {
+            let roman = romanize(text, scheme);
+            assert_eq!(
+                to_sinhala(&roman, scheme),
+                without_touching_zwj(text),
+                "{text} in {} reads {roman}",
+                scheme.label()
+            );
+        }
+    }
+
+    #[test]
+    fn decks_round_trip_in_every_scheme() {
+        let files = deck::DECK_FILES.iter().map(|file| {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("assets")
+                .join(file);
+            deck::Deck::from_ron(&fs::read(path).unwrap()).unwrap()
+        });
+        for deck in deck::generated().into_iter().chain(files) {
+            for card in &deck.cards {
+                assert_round_trips(card.id());
+            }
+        }
+    }
+
+    #[test]
+    fn letters_that_run_together_round_trip() {
+        for text in ["ක්හ", "ජ්ඤ", "න්ග", "ස්හ", "ලෘ", "ලෘඋ", "කඅ", "අඅ", "අඉ", "ඉඉ"]
+        {
+            assert_round_trips(text);
+        }
+        assert_eq!(romanize("ක්හ", RomanizationScheme::Iso15919), "k:ha");
+        assert_eq!(romanize("ඛ", RomanizationScheme::Iso15919), "kha");
+        assert_eq!(romanize("අඅ", RomanizationScheme::Ascii), "a:a");
+    }
+
+    #[test]
+    fn romanizes_inherent_vowel_and_hal_kirima() {
+        assert_eq!(romanize("බත්", RomanizationScheme::Iso15919), "bat");
+        assert_eq!(romanize("කා", RomanizationScheme::Ascii), "kaa");
+        assert_eq!(romanize("ක්\u{200D}ර", RomanizationScheme::Iso15919), "kra");
+    }
+
+    #[test]
+    fn clusters_keep_conjuncts_together() {
+        assert_eq!(clusters("ක්\u{200D}රම"), ["ක්\u{200D}ර", "ම"]);
+    }
+}
@@ -0,0 +1,1276 @@
+//! The Bevy front end: shows the questions of a [`QuizEngine`] and feeds it the answers.
+
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+use sinhala_training::{
+    engine::{AnswerMode, Outcome, QuizEngine, SessionLength},
+    history::Attempt,
+    ime,
+    matching::MatchResult,
+    pair::Field,
+    persistence::{self, SaveData},
+    scheduler,
+};
+
+use crate::{
+    deck_asset::{ActiveDeck, DeckAsset, RestoredDeck},
+    layout::Orientation,
+    screens::AppState,
+    settings::{Panel, Settings},
+};
+
+const FOCUSED_BORDER: Color = Color::srgb(1.0, 0.8, 0.0);
+/// Shortcuts for the first answer buttons, used while there are no more buttons than keys.
+const DIGIT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+#[derive(Debug, Component)]
+struct QuestionText;
+#[derive(Debug, Component)]
+struct AnswerText;
+/// The grid of answer buttons, laid out by [`layout_answer_grid`].
+#[derive(Debug, Component)]
+struct AnswerBox;
+/// An answer button, showing the option with this index.
+#[derive(Debug, Component)]
+struct AnswerButton(usize);
+#[derive(Debug, Component)]
+struct LeitnerText;
+#[derive(Debug, Component)]
+struct DeckText;
+#[derive(Debug, Component)]
+struct QuizModeText;
+#[derive(Debug, Component)]
+struct TypedAnswerBox;
+#[derive(Debug, Component)]
+struct TypedAnswerField;
+#[derive(Debug, Component)]
+struct TypedAnswerText;
+#[derive(Debug, Component)]
+struct CompositionText;
+#[derive(Debug, Component)]
+struct HintText;
+#[derive(Debug, Component)]
+struct SessionText;
+/// The bar above the answers, laid out for the window's [`Orientation`] by [`apply_orientation`].
+#[derive(Debug, Component)]
+struct TopBar;
+/// The middle of the [`TopBar`], showing the question.
+#[derive(Debug, Component)]
+struct QuestionPanel;
+#[derive(Debug, Clone, Copy, Component)]
+enum QuizButton {
+    SwitchQuizMode,
+    RerollQuestions,
+    EndSession,
+}
+
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct Quiz(pub QuizEngine);
+#[derive(Debug, Default, Resource, Deref, DerefMut)]
+struct TypedAnswer(String);
+/// The answer button chosen with the keyboard or a gamepad, once either has been used.
+#[derive(Debug, Default, Resource, Deref, DerefMut)]
+struct AnswerFocus(Option<usize>);
+/// How many columns the answer grid has, for moving the focus up and down.
+#[derive(Debug, Resource, Deref, DerefMut)]
+struct AnswerColumns(usize);
+
+impl Default for AnswerColumns {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+/// What kind of session entering [`AppState::Quiz`] starts.
+#[derive(Debug, Default, Resource)]
+pub enum NextSession {
+    #[default]
+    New,
+    RetryMissed,
+}
+/// Card ids of the active set from the last session, applied once the deck has loaded.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct RestoredActiveSet(pub Vec<String>);
+
+#[derive(Event)]
+struct AnsweredEvent(pub Entity);
+#[derive(Event)]
+struct RestartEvent;
+#[derive(Event)]
+struct RerollQuestionsEvent;
+#[derive(Event)]
+struct TypedAnswerEvent(pub String);
+#[derive(Event)]
+struct QuizActionEvent(QuizButton);
+
+/// The quiz screen, shown in [`AppState::Quiz`]. Needs the [`Quiz`] resource, and optionally
+/// a [`RestoredActiveSet`].
+pub struct QuizUiPlugin;
+
+impl Plugin for QuizUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnsweredEvent>()
+            .add_event::<RestartEvent>()
+            .add_event::<RerollQuestionsEvent>()
+            .add_event::<TypedAnswerEvent>()
+            .add_event::<QuizActionEvent>()
+            .init_resource::<TypedAnswer>()
+            .init_resource::<AnswerFocus>()
+            .init_resource::<AnswerColumns>()
+            .init_resource::<NextSession>()
+            .add_systems(OnEnter(AppState::Quiz), (start_session, spawn_quiz_screen))
+            .add_systems(
+                Update,
+                (
+                    apply_deck.run_if(not(resource_exists::<RestoredDeck>)),
+                    (
+                        quiz_button_system,
+                        quiz_hotkeys,
+                        handle_quiz_action,
+                        next_question_after_feedback,
+                        reroll_questions,
+                        setup_question,
+                        reset_typed_answer,
+                        button_system,
+                        answer_navigation,
+                        show_answer_focus,
+                        typed_answer_input,
+                        handle_answer,
+                        handle_typed_answer,
+                        update_quiz_mode_text,
+                        update_leitner_text,
+                        update_session_text,
+                        apply_answer_mode,
+                        apply_orientation,
+                        layout_answer_grid,
+                    )
+                        .chain()
+                        .run_if(in_state(AppState::Quiz)),
+                    save_progress,
+                )
+                    .chain(),
+            );
+    }
+}
+
+pub fn field_font(field: Field, asset_server: &AssetServer) -> Handle<Font> {
+    match field {
+        Field::Sinhala => {
+            asset_server.load("fonts/Noto_Sans_Sinhala/NotoSansSinhala-VariableFont_wdth,wght.ttf")
+        }
+        Field::Romanization | Field::Ipa | Field::Gloss => {
+            asset_server.load("fonts/Noto_Serif/NotoSerif-VariableFont_wdth,wght.ttf")
+        }
+    }
+}
+
+fn apply_deck(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<DeckAsset>>,
+    decks: Res<Assets<DeckAsset>>,
+    active_deck: Res<ActiveDeck>,
+    mut quiz: ResMut<Quiz>,
+    restored_set: Option<Res<RestoredActiveSet>>,
+    mut settings: ResMut<Settings>,
+    mut deck_text: Query<&mut Text, With<DeckText>>,
+    mut restart: EventWriter<RestartEvent>,
+) {
+    let mut reload = active_deck.is_changed();
+    for event in asset_events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event {
+            reload |= *id == active_deck.id();
+        }
+    }
+    if !reload {
+        return;
+    }
+    // Files may still be loading, in which case the loaded event brings us back here.
+    let Some(deck) = decks.get(&active_deck.0) else {
+        return;
+    };
+
+    quiz.set_cards(deck.cards.clone());
+    // Remembered by name only once loaded, as files don't know theirs before.
+    if settings.deck.as_ref() != Some(&deck.name) {
+        settings.deck = Some(deck.name.clone());
+    }
+    if let Ok(mut deck_text) = deck_text.get_single_mut() {
+        deck_text.sections[0].value = deck.name.clone();
+    }
+    if let Some(restored_set) = &restored_set {
+        quiz.restore_active_set(restored_set);
+        commands.remove_resource::<RestoredActiveSet>();
+    }
+    restart.send(RestartEvent);
+}
+
+/// Moves on once the result of an answer has been up for the feedback delay.
+fn next_question_after_feedback(
+    mut could_answer: Local<f32>,
+    time: Res<Time>,
+    quiz: Res<Quiz>,
+    settings: Res<Settings>,
+    mut event_writer: EventWriter<RestartEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !quiz.is_answered() {
+        *could_answer = time.elapsed_seconds();
+        // Nothing was left to ask, like when retrying misses that have all left the deck.
+        if quiz.is_session_over(scheduler::now()) {
+            next_state.set(AppState::Results);
+        }
+    } else if *could_answer + settings.feedback_delay < time.elapsed_seconds() {
+        if quiz.is_session_over(scheduler::now()) {
+            next_state.set(AppState::Results);
+        } else {
+            event_writer.send(RestartEvent);
+        }
+        *could_answer = time.elapsed_seconds();
+    }
+}
+
+fn reroll_questions(
+    mut event_reader: EventReader<RerollQuestionsEvent>,
+    mut quiz: ResMut<Quiz>,
+    mut event_writer: EventWriter<RestartEvent>,
+) {
+    for _ in event_reader.read() {
+        quiz.reroll(&mut rand::thread_rng());
+        event_writer.send(RestartEvent);
+    }
+}
+
+fn setup_question(
+    mut commands: Commands,
+    mut event_reader: EventReader<RestartEvent>,
+    mut question_text: Query<&mut Text, (With<QuestionText>, Without<AnswerText>)>,
+    mut hint_text: Query<&mut Text, (With<HintText>, Without<QuestionText>, Without<AnswerText>)>,
+    mut quiz: ResMut<Quiz>,
+    mut buttons: Query<(
+        Entity,
+        &AnswerButton,
+        &mut BackgroundColor,
+        &mut BorderColor,
+        &Children,
+    )>,
+    mut answer_texts: Query<&mut Text, (With<AnswerText>, Without<QuestionText>)>,
+    answer_box: Query<Entity, With<AnswerBox>>,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    // Several restarts in a frame, like starting a session and loading a deck, ask one
+    // question: the buttons spawned here only show up in the query next frame.
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    let Some(question) = quiz.next_question(&mut rand::thread_rng(), scheduler::now()) else {
+        return;
+    };
+    let palette = settings.palette();
+    let mut question_text = question_text.single_mut();
+    question_text.sections[0].value = question.prompt;
+    question_text.sections[0].style.font = field_font(quiz.quiz_mode.prompt_field, &asset_server);
+    hint_text.single_mut().sections[0].value = question.hint;
+
+    let font = field_font(quiz.quiz_mode.answer_field, &asset_server);
+    let mut existing = 0;
+    for (entity, &AnswerButton(index), mut color, mut border_color, children) in &mut buttons {
+        // Fewer options than before leave buttons over.
+        let Some(option) = question.options.get(index) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        existing += 1;
+        color.0 = palette.button;
+        border_color.0 = Color::BLACK;
+        if let Ok(mut text) = answer_texts.get_mut(children[0]) {
+            let section = &mut text.sections[0];
+            section.value = option.clone();
+            section.style.font = font.clone();
+        }
+    }
+    if existing < question.options.len() {
+        commands
+            .entity(answer_box.single())
+            .with_children(|commands| {
+                for (index, option) in question.options.iter().enumerate().skip(existing) {
+                    spawn_answer_button(commands, index, option, font.clone(), &settings);
+                }
+            });
+    }
+}
+
+fn start_session(
+    mut quiz: ResMut<Quiz>,
+    mut next_session: ResMut<NextSession>,
+    mut restart: EventWriter<RestartEvent>,
+) {
+    match std::mem::take(&mut *next_session) {
+        NextSession::New => quiz.start_session(scheduler::now()),
+        NextSession::RetryMissed => quiz.retry_missed(scheduler::now()),
+    }
+    restart.send(RestartEvent);
+}
+
+fn spawn_quiz_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    quiz: Res<Quiz>,
+    active_deck: Res<ActiveDeck>,
+    decks: Res<Assets<DeckAsset>>,
+    orientation: Res<Orientation>,
+    settings: Res<Settings>,
+) {
+    let palette = settings.palette();
+    let deck_name = decks
+        .get(&active_deck.0)
+        .map(|deck| deck.name.clone())
+        .unwrap_or_default();
+    let (answer_box_display, typed_box_display) = answer_box_displays(quiz.answer_mode);
+
+    let toplevel = commands
+        .spawn((
+            StateScoped(AppState::Quiz),
+            NodeBundle {
+                style: Style {
+                    display: Display::Flex,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .id();
+
+    let mut top_style = Style {
+        display: Display::Grid,
+        width: Val::Percent(100.0),
+        ..default()
+    };
+    let mut question_style = Style {
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    layout_top_bar(*orientation, &mut top_style, &mut question_style);
+
+    let top = commands
+        .spawn((
+            TopBar,
+            Panel,
+            NodeBundle {
+                style: top_style,
+                ..default()
+            },
+        ))
+        .with_children(|commands| {
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Flex,
+                        height: Val::Percent(100.0),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Start,
+                        align_items: AlignItems::Start,
+                        padding: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|commands| {
+                    commands
+                        .spawn((
+                            QuizButton::SwitchQuizMode,
+                            ButtonBundle {
+                                style: Style {
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                        ))
+                        .with_children(|commands| {
+                            commands.spawn((
+                                QuizModeText,
+                                TextBundle::from_section(
+                                    quiz.quiz_mode.label(),
+                                    TextStyle {
+                                        font: asset_server.load(
+                                            "fonts/Noto_Sans_Sinhala/NotoSansSinhala-VariableFont_wdth,wght.ttf",
+                                        ),
+                                        font_size: 50.0,
+                                        ..default()
+                                    },
+                                ),
+                            ));
+                        });
+                    commands.spawn((
+                        DeckText,
+                        TextBundle::from_section(
+                            deck_name,
+                            TextStyle {
+                                font: asset_server.load(
+                                    "fonts/Noto_Sans_Sinhala/NotoSansSinhala-VariableFont_wdth,wght.ttf",
+                                ),
+                                font_size: 30.0,
+                                color: Color::srgb(0.8, 0.8, 0.8),
+                                ..default()
+                            },
+                        ),
+                    ));
+                    commands.spawn((
+                        SessionText,
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server
+                                    .load("fonts/Noto_Serif/NotoSerif-VariableFont_wdth,wght.ttf"),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                        ),
+                    ));
+                    commands.spawn((
+                        LeitnerText,
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server
+                                    .load("fonts/Noto_Serif/NotoSerif-VariableFont_wdth,wght.ttf"),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                        ),
+                    ));
+                });
+
+            commands.spawn((
+                QuestionPanel,
+                NodeBundle {
+                    style: question_style,
+                    ..default()
+                },
+            ))
+            .with_children(|commands| {
+                commands.spawn((
+                    QuestionText,
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load(
+                                "fonts/Noto_Sans_Sinhala/NotoSansSinhala-VariableFont_wdth,wght.ttf",
+                            ),
+                            font_size: 75.0,
+                            ..default()
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                ));
+                commands.spawn((
+                    HintText,
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server
+                                .load("fonts/Noto_Serif/NotoSerif-VariableFont_wdth,wght.ttf"),
+                            font_size: 30.0,
+                            color: Color::srgb(0.8, 0.8, 0.8),
+                            ..default()
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                ));
+                commands.spawn((
+                    CompositionText,
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load(
+                                "fonts/Noto_Sans_Sinhala/NotoSansSinhala-VariableFont_wdth,wght.ttf",
+                            ),
+                            font_size: 40.0,
+                            color: Color::srgb(0.8, 0.8, 0.8),
+                            ..default()
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                ));
+            });
+
+            commands.spawn(NodeBundle {
+                style: Style {
+                    display: Display::Flex,
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexStart,
+                    align_items: AlignItems::FlexEnd,
+                    padding: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                ..default()
+            }) .with_children(|commands| {
+                commands
+                    .spawn((
+                        QuizButton::RerollQuestions,
+                        ButtonBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ))
+                    .with_children(|commands| {
+                        commands.spawn((TextBundle::from_section(
+                            " ",
+                            TextStyle {
+                                font: asset_server.load(
+                                    "fonts/0xProto/0xProtoNerdFont-Regular.ttf",
+                                ),
+                                font_size: 50.0,
+                                ..default()
+                            },
+                        ),));
+                    });
+                commands
+                    .spawn((
+                        QuizButton::EndSession,
+                        ButtonBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ))
+                    .with_children(|commands| {
+                        commands.spawn((TextBundle::from_section(
+                            "End",
+                            TextStyle {
+                                font: asset_server.load(
+                                    "fonts/Noto_Serif/NotoSerif-VariableFont_wdth,wght.ttf",
+                                ),
+                                font_size: 30.0,
+                                ..default()
+                            },
+                        ),));
+                    });
+            });
+        })
+        .id();
+
+    let bottom = commands
+        .spawn((
+            AnswerBox,
+            NodeBundle {
+                style: Style {
+                    display: answer_box_display,
+                    flex_grow: 3.0,
+                    width: Val::Percent(100.0),
+                    grid_template_rows: vec![RepeatedGridTrack::flex(1, 1.0)],
+                    grid_template_columns: vec![RepeatedGridTrack::flex(1, 1.0)],
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .id();
+
+    let typed = commands
+        .spawn((
+            TypedAnswerBox,
+            NodeBundle {
+                style: Style {
+                    display: typed_box_display,
+                    flex_grow: 3.0,
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|commands| {
+            commands
+                .spawn((
+                    TypedAnswerField,
+                    NodeBundle {
+                        style: Style {
+                            min_width: Val::Percent(40.0),
+                            border: UiRect::all(Val::Px(5.0)),
+                            padding: UiRect::all(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        border_color: BorderColor(Color::BLACK),
+                        background_color: palette.button.into(),
+                        ..default()
+                    },
+                ))
+                .with_children(|commands| {
+                    commands.spawn((
+                        TypedAnswerText,
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server
+                                    .load("fonts/Noto_Serif/NotoSerif-VariableFont_wdth,wght.ttf"),
+                                font_size: 75.0,
+                                ..default()
+                            },
+                        ),
+                    ));
+                });
+        })
+        .id();
+
+    commands
+        .entity(toplevel)
+        .add_children(&[top, bottom, typed]);
+}
+
+fn spawn_answer_button(
+    commands: &mut ChildBuilder,
+    index: usize,
+    option: &str,
+    font: Handle<Font>,
+    settings: &Settings,
+) {
+    commands
+        .spawn((
+            AnswerButton(index),
+            ButtonBundle {
+                style: Style {
+                    //width: Val::Px(150.0),
+                    //height: Val::Px(65.0),
+                    border: UiRect::all(Val::Px(5.0)),
+                    margin: UiRect::all(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                border_color: BorderColor(Color::BLACK),
+                background_color: settings.palette().button.into(),
+                ..default()
+            },
+        ))
+        .with_children(|commands| {
+            commands.spawn((
+                AnswerText,
+                TextBundle::from_section(
+                    option,
+                    TextStyle {
+                        font,
+                        font_size: 75.0,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (Entity, &Interaction, &mut BackgroundColor, &mut BorderColor),
+        (Changed<Interaction>, With<AnswerButton>),
+    >,
+    mut answered: EventWriter<AnsweredEvent>,
+    quiz: Res<Quiz>,
+    settings: Res<Settings>,
+) {
+    let palette = settings.palette();
+    for (entity, interaction, mut color, mut border_color) in &mut interaction_query {
+        if !quiz.is_answered() {
+            match *interaction {
+                Interaction::Pressed => {
+                    //text.sections[0].value = "Press".to_string();
+                    *color = palette.pressed_button.into();
+                    //border_color.0 = Color::srgb(1.0, 0.0, 0.0);
+                    answered.send(AnsweredEvent(entity));
+                }
+                Interaction::Hovered => {
+                    //text.sections[0].value = "Hover".to_string();
+                    *color = palette.hovered_button.into();
+                    border_color.0 = Color::WHITE;
+                }
+                Interaction::None => {
+                    //text.sections[0].value = "Button".to_string();
+                    *color = palette.button.into();
+                    border_color.0 = Color::BLACK;
+                }
+            }
+        }
+    }
+}
+
+/// Moves the focus over the answer grid with the arrow keys, WASD or a D-pad, and answers
+/// with Enter, Space or the gamepad's south button. With few options, the number keys answer
+/// directly.
+fn answer_navigation(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    buttons: Query<(Entity, &AnswerButton)>,
+    columns: Res<AnswerColumns>,
+    mut focus: ResMut<AnswerFocus>,
+    mut answered: EventWriter<AnsweredEvent>,
+    quiz: Res<Quiz>,
+) {
+    if quiz.answer_mode != AnswerMode::MultipleChoice || quiz.is_answered() {
+        return;
+    }
+    let options = buttons.iter().count();
+    if options == 0 {
+        return;
+    }
+
+    let key = |codes: &[KeyCode]| keys.any_just_pressed(codes.iter().copied());
+    let pad = |button: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+    let columns = columns.0.max(1);
+    let current = focus.unwrap_or_default().min(options - 1);
+    let moved = if key(&[KeyCode::ArrowLeft, KeyCode::KeyA]) || pad(GamepadButtonType::DPadLeft) {
+        Some((current + options - 1) % options)
+    } else if key(&[KeyCode::ArrowRight, KeyCode::KeyD]) || pad(GamepadButtonType::DPadRight) {
+        Some((current + 1) % options)
+    } else if key(&[KeyCode::ArrowUp, KeyCode::KeyW]) || pad(GamepadButtonType::DPadUp) {
+        Some(current.checked_sub(columns).unwrap_or(current))
+    } else if key(&[KeyCode::ArrowDown, KeyCode::KeyS]) || pad(GamepadButtonType::DPadDown) {
+        Some(if current + columns < options {
+            current + columns
+        } else {
+            current
+        })
+    } else {
+        None
+    };
+    if let Some(moved) = moved {
+        // The first press only shows where the focus is.
+        focus.0 = Some(if focus.is_some() { moved } else { current });
+        return;
+    }
+
+    let chosen = if options <= DIGIT_KEYS.len() {
+        DIGIT_KEYS[..options]
+            .iter()
+            .position(|&digit| keys.just_pressed(digit))
+    } else {
+        None
+    };
+    let confirmed = key(&[KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space])
+        || pad(GamepadButtonType::South);
+    let Some(chosen) = chosen.or(focus.filter(|_| confirmed)) else {
+        return;
+    };
+    if let Some((entity, _)) = buttons.iter().find(|(_, button)| button.0 == chosen) {
+        focus.0 = Some(chosen);
+        answered.send(AnsweredEvent(entity));
+    }
+}
+
+/// Marks the focused answer button, and restores the others.
+fn show_answer_focus(
+    focus: Res<AnswerFocus>,
+    quiz: Res<Quiz>,
+    added: Query<(), Added<AnswerButton>>,
+    mut buttons: Query<(&AnswerButton, &mut BackgroundColor, &mut BorderColor)>,
+    settings: Res<Settings>,
+) {
+    let changed = focus.is_changed() || quiz.is_changed() || !added.is_empty();
+    if !changed || quiz.is_answered() {
+        return;
+    }
+    let palette = settings.palette();
+    for (&AnswerButton(index), mut color, mut border_color) in &mut buttons {
+        if focus.0 == Some(index) {
+            color.0 = palette.hovered_button;
+            border_color.0 = FOCUSED_BORDER;
+        } else if border_color.0 == FOCUSED_BORDER {
+            color.0 = palette.button;
+            border_color.0 = Color::BLACK;
+        }
+    }
+}
+
+/// Tab switches the quiz mode, F5 rerolls the questions and Escape ends the session, as do
+/// the gamepad's north, west and start buttons.
+fn quiz_hotkeys(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut actions: EventWriter<QuizActionEvent>,
+) {
+    let pad = |button: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+    for (key, button, action) in [
+        (
+            KeyCode::Tab,
+            GamepadButtonType::North,
+            QuizButton::SwitchQuizMode,
+        ),
+        (
+            KeyCode::F5,
+            GamepadButtonType::West,
+            QuizButton::RerollQuestions,
+        ),
+        (
+            KeyCode::Escape,
+            GamepadButtonType::Start,
+            QuizButton::EndSession,
+        ),
+    ] {
+        if keys.just_pressed(key) || pad(button) {
+            actions.send(QuizActionEvent(action));
+        }
+    }
+}
+
+fn quiz_button_system(
+    mut interaction_query: Query<(&Interaction, &Children, &QuizButton), Changed<Interaction>>,
+    mut text: Query<&mut Text>,
+    mut actions: EventWriter<QuizActionEvent>,
+) {
+    for (interaction, children, setting) in &mut interaction_query {
+        let text = &mut text.get_mut(children[0]).unwrap().sections[0];
+        text.style.color = match *interaction {
+            Interaction::Pressed => Color::srgb(1.0, 1.0, 1.0),
+            Interaction::Hovered => Color::srgb(0.9, 0.9, 0.9),
+            Interaction::None => Color::srgb(0.8, 0.8, 0.8),
+        };
+        if *interaction == Interaction::Pressed {
+            actions.send(QuizActionEvent(*setting));
+        }
+    }
+}
+
+fn handle_quiz_action(
+    mut actions: EventReader<QuizActionEvent>,
+    mut reroll_questions: EventWriter<RerollQuestionsEvent>,
+    mut restart: EventWriter<RestartEvent>,
+    mut quiz: ResMut<Quiz>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for QuizActionEvent(action) in actions.read() {
+        match action {
+            QuizButton::SwitchQuizMode => {
+                quiz.switch_quiz_mode();
+                restart.send(RestartEvent);
+            }
+            QuizButton::RerollQuestions => {
+                reroll_questions.send(RerollQuestionsEvent);
+            }
+            QuizButton::EndSession => {
+                next_state.set(AppState::Results);
+            }
+        }
+    }
+}
+
+fn handle_answer(
+    mut buttons: Query<(
+        Entity,
+        &AnswerButton,
+        &mut BackgroundColor,
+        &mut BorderColor,
+    )>,
+    mut answered: ResMut<Events<AnsweredEvent>>,
+    mut quiz: ResMut<Quiz>,
+    settings: Res<Settings>,
+) {
+    let palette = settings.palette();
+    for AnsweredEvent(answered_entity) in answered.drain().take(1) {
+        let Some(answer) = buttons
+            .get(answered_entity)
+            .ok()
+            .and_then(|(_, &AnswerButton(index), ..)| quiz.options().get(index).cloned())
+        else {
+            continue;
+        };
+        let Some(outcome) = quiz.submit_answer(&answer, scheduler::now()) else {
+            continue;
+        };
+        record_attempt(&outcome);
+
+        let correct_option = quiz.correct_option();
+        for (entity, &AnswerButton(index), mut color, mut border_color) in &mut buttons {
+            if entity == answered_entity {
+                *color = palette.pressed_button.into();
+                if outcome.is_correct() {
+                    border_color.0 = Color::srgb(0.0, 1.0, 0.0);
+                } else {
+                    border_color.0 = Color::srgb(1.0, 0.0, 0.0);
+                }
+            } else if Some(index) == correct_option {
+                *color = palette.button.into();
+                border_color.0 = Color::srgb(0.0, 0.0, 1.0);
+            } else {
+                *color = palette.button.into();
+                border_color.0 = Color::BLACK;
+            }
+        }
+    }
+}
+
+fn record_attempt(outcome: &Outcome) {
+    if let Err(err) = persistence::record(&Attempt::from(outcome)) {
+        warn!("{err}");
+    }
+}
+
+fn reset_typed_answer(
+    mut event_reader: EventReader<RestartEvent>,
+    mut typed_answer: ResMut<TypedAnswer>,
+    mut field: Query<&mut BorderColor, With<TypedAnswerField>>,
+    mut typed_text: Query<&mut Text, With<TypedAnswerText>>,
+    quiz: Res<Quiz>,
+    asset_server: Res<AssetServer>,
+) {
+    for _ in event_reader.read() {
+        typed_answer.clear();
+        field.single_mut().0 = Color::BLACK;
+        typed_text.single_mut().sections[0].style.font =
+            field_font(quiz.quiz_mode.answer_field, &asset_server);
+    }
+}
+
+fn typed_answer_input(
+    mut keyboard: EventReader<KeyboardInput>,
+    mut typed_answer: ResMut<TypedAnswer>,
+    mut typed_text: Query<&mut Text, (With<TypedAnswerText>, Without<CompositionText>)>,
+    mut composition_text: Query<&mut Text, (With<CompositionText>, Without<TypedAnswerText>)>,
+    mut submitted: EventWriter<TypedAnswerEvent>,
+    quiz: Res<Quiz>,
+) {
+    // Sinhala answers are typed phonetically and composed by the input method.
+    let composing = quiz.quiz_mode.answer_field == Field::Sinhala;
+    let typing = quiz.answer_mode == AnswerMode::Typed;
+
+    if typing && !quiz.is_answered() {
+        for event in keyboard.read() {
+            if !event.state.is_pressed() {
+                continue;
+            }
+            match &event.logical_key {
+                Key::Enter => {
+                    let answer = if composing {
+                        ime::transliterate(&typed_answer)
+                    } else {
+                        typed_answer.0.clone()
+                    };
+                    submitted.send(TypedAnswerEvent(answer));
+                }
+                Key::Backspace => {
+                    typed_answer.pop();
+                }
+                Key::Space => typed_answer.push(' '),
+                Key::Character(character) => typed_answer.push_str(character),
+                _ => {}
+            }
+        }
+    } else {
+        keyboard.clear();
+    }
+
+    // Once answered, the field shows the correction from `handle_typed_answer` instead.
+    if (typed_answer.is_changed() || quiz.is_changed()) && !quiz.is_answered() {
+        typed_text.single_mut().sections[0].value = typed_answer.0.clone();
+        composition_text.single_mut().sections[0].value = if composing && typing {
+            ime::transliterate(&typed_answer)
+        } else {
+            String::new()
+        };
+    }
+}
+
+fn handle_typed_answer(
+    mut submitted: EventReader<TypedAnswerEvent>,
+    mut field: Query<&mut BorderColor, With<TypedAnswerField>>,
+    mut typed_text: Query<&mut Text, With<TypedAnswerText>>,
+    mut quiz: ResMut<Quiz>,
+) {
+    for TypedAnswerEvent(answer) in submitted.read().take(1) {
+        let Some(outcome) = quiz.submit_answer(answer, scheduler::now()) else {
+            continue;
+        };
+        record_attempt(&outcome);
+
+        field.single_mut().0 = match outcome.result {
+            MatchResult::Correct => Color::srgb(0.0, 1.0, 0.0),
+            MatchResult::Close => Color::srgb(1.0, 1.0, 0.0),
+            MatchResult::Incorrect => Color::srgb(1.0, 0.0, 0.0),
+        };
+        if !outcome.is_correct() {
+            typed_text.single_mut().sections[0].value =
+                format!("{answer} -> {}", outcome.correct_answer);
+        }
+    }
+}
+
+/// Lays the [`TopBar`] out side by side in landscape. In portrait, the controls share a row
+/// above the question, which gets the full width.
+fn layout_top_bar(orientation: Orientation, top: &mut Style, question: &mut Style) {
+    match orientation {
+        Orientation::Landscape => {
+            top.flex_grow = 1.0;
+            top.grid_template_rows = vec![RepeatedGridTrack::percent(1, 100.0)];
+            top.grid_template_columns = vec![RepeatedGridTrack::percent(3, 100.0 / 3.0)];
+            question.grid_row = GridPlacement::auto();
+            question.grid_column = GridPlacement::auto();
+        }
+        Orientation::Portrait => {
+            top.flex_grow = 2.0;
+            top.grid_template_rows =
+                vec![RepeatedGridTrack::auto(1), RepeatedGridTrack::flex(1, 1.0)];
+            top.grid_template_columns = vec![RepeatedGridTrack::flex(2, 1.0)];
+            question.grid_row = GridPlacement::start(2);
+            question.grid_column = GridPlacement::span(2);
+        }
+    }
+}
+
+fn apply_orientation(
+    orientation: Res<Orientation>,
+    mut top: Query<&mut Style, (With<TopBar>, Without<QuestionPanel>)>,
+    mut question: Query<&mut Style, (With<QuestionPanel>, Without<TopBar>)>,
+) {
+    if !orientation.is_changed() {
+        return;
+    }
+    if let (Ok(mut top), Ok(mut question)) = (top.get_single_mut(), question.get_single_mut()) {
+        layout_top_bar(*orientation, &mut top, &mut question);
+    }
+}
+
+/// Rows and columns for `options` answer buttons in a box `aspect_ratio` times as wide as it
+/// is high. Of the column counts that keep the buttons roughly square, takes the one leaving
+/// the fewest empty cells, and of those the squarest.
+fn grid_shape(options: usize, aspect_ratio: f32) -> (usize, usize) {
+    let options = options.max(1);
+    let square = (options as f32 * aspect_ratio).sqrt();
+    let fewest = ((square / 1.5).ceil() as usize).clamp(1, options);
+    let most = ((square * 1.5).floor() as usize).clamp(fewest, options);
+    let columns = (fewest..=most)
+        .map(|columns| {
+            let empty = options.div_ceil(columns) * columns - options;
+            (columns, empty, (columns as f32 / square).ln().abs())
+        })
+        .min_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)))
+        .map_or(1, |(columns, ..)| columns);
+    (options.div_ceil(columns), columns)
+}
+
+/// Fits the answer grid to the number of options and the space it has, shrinking the text
+/// when the buttons get small.
+fn layout_answer_grid(
+    mut answer_box: Query<(&mut Style, &Node), With<AnswerBox>>,
+    buttons: Query<&Children, With<AnswerButton>>,
+    mut answer_texts: Query<&mut Text, With<AnswerText>>,
+    mut answer_columns: ResMut<AnswerColumns>,
+) {
+    let (mut style, node) = answer_box.single_mut();
+    let size = node.size();
+    if size.y <= 0.0 {
+        return;
+    }
+
+    let (rows, columns) = grid_shape(buttons.iter().count(), size.x / size.y);
+    if answer_columns.0 != columns {
+        answer_columns.0 = columns;
+    }
+    let template_rows = vec![RepeatedGridTrack::flex(rows as u16, 1.0)];
+    let template_columns = vec![RepeatedGridTrack::flex(columns as u16, 1.0)];
+    if style.grid_template_rows != template_rows || style.grid_template_columns != template_columns
+    {
+        style.grid_template_rows = template_rows;
+        style.grid_template_columns = template_columns;
+    }
+
+    // Leave room for the button's margin and border.
+    let font_size = ((size.y / rows as f32 - 30.0) * 0.6).clamp(16.0, 75.0);
+    for children in &buttons {
+        if let Ok(mut text) = answer_texts.get_mut(children[0]) {
+            if text.sections[0].style.font_size != font_size {
+                text.sections[0].style.font_size = font_size;
+            }
+        }
+    }
+}
+
+/// How to show the answer buttons and the typed answer field.
+fn answer_box_displays(answer_mode: AnswerMode) -> (Display, Display) {
+    match answer_mode {
+        AnswerMode::MultipleChoice => (Display::Grid, Display::None),
+        AnswerMode::Typed => (Display::None, Display::Flex),
+    }
+}
+
+fn apply_answer_mode(
+    quiz: Res<Quiz>,
+    mut answer_box: Query<&mut Style, (With<AnswerBox>, Without<TypedAnswerBox>)>,
+    mut typed_box: Query<&mut Style, (With<TypedAnswerBox>, Without<AnswerBox>)>,
+) {
+    if !quiz.is_changed() {
+        return;
+    }
+
+    let (answer_box_display, typed_box_display) = answer_box_displays(quiz.answer_mode);
+    answer_box.single_mut().display = answer_box_display;
+    typed_box.single_mut().display = typed_box_display;
+}
+
+fn update_quiz_mode_text(
+    mut quiz_mode_text: Query<&mut Text, With<QuizModeText>>,
+    quiz: Res<Quiz>,
+) {
+    if quiz.is_changed() {
+        quiz_mode_text.single_mut().sections[0].value = quiz.quiz_mode.label();
+    }
+}
+
+fn update_leitner_text(mut leitner_text: Query<&mut Text, With<LeitnerText>>, quiz: Res<Quiz>) {
+    if !quiz.is_changed() {
+        return;
+    }
+
+    leitner_text.single_mut().sections[0].value = quiz
+        .stats()
+        .leitner_boxes
+        .iter()
+        .enumerate()
+        .map(|(i, count)| format!("{}: {count}", i + 1))
+        .collect::<Vec<_>>()
+        .join("  ");
+}
+
+/// Questions or time left in the session, and how long answers take on average.
+fn update_session_text(mut session_text: Query<&mut Text, With<SessionText>>, quiz: Res<Quiz>) {
+    let session = quiz.session();
+    let stats = quiz.stats();
+    let mut text = match session.length {
+        SessionLength::Questions(questions) => format!("{} / {questions}", stats.answered),
+        SessionLength::Minutes(minutes) => {
+            let elapsed = scheduler::now() - session.started_at;
+            let left = (f64::from(minutes) * 60.0 - elapsed).max(0.0).ceil() as u64;
+            format!("{}:{:02} left", left / 60, left % 60)
+        }
+    };
+    if let Some(response_time) = stats.average_response_time() {
+        text += &format!("  {response_time:.1} s");
+    }
+    let section = &mut session_text.single_mut().sections[0];
+    if section.value != text {
+        section.value = text;
+    }
+}
+
+fn save_progress(quiz: Res<Quiz>, settings: Res<Settings>) {
+    // Nothing worth saving until the deck has loaded and filled the active set.
+    if !(quiz.is_changed() || settings.is_changed()) || quiz.active_set().is_empty() {
+        return;
+    }
+
+    if let Err(err) = persistence::save(&SaveData::from_engine(&quiz, &settings)) {
+        warn!("{err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sinhala_training::{
+        deck, leitner::Leitner, preferences::Preferences, scheduler::Scheduler,
+    };
+
+    use super::*;
+
+    #[test]
+    fn restarts_in_one_frame_spawn_one_set_of_buttons() {
+        let mut engine = QuizEngine::new(Scheduler::default(), Leitner::default());
+        engine.set_cards(deck::generated()[0].cards.clone());
+        engine.start_session(scheduler::now());
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Font>()
+            .add_event::<RestartEvent>()
+            .insert_resource(Quiz(engine))
+            .insert_resource(Settings(Preferences::default()))
+            .add_systems(Update, setup_question);
+        let world = app.world_mut();
+        world.spawn((QuestionText, TextBundle::default()));
+        world.spawn((HintText, TextBundle::default()));
+        world.spawn((AnswerBox, NodeBundle::default()));
+        world.send_event(RestartEvent);
+        world.send_event(RestartEvent);
+        app.update();
+
+        let world = app.world_mut();
+        let options = world.resource::<Quiz>().options();
+        let mut buttons = world.query::<(&AnswerButton, &Children)>();
+        let mut shown = vec![None; options.len()];
+        for (&AnswerButton(index), children) in buttons.iter(world) {
+            let text = world.get::<Text>(children[0]).unwrap();
+            assert!(shown[index].is_none(), "two buttons for option {index}");
+            shown[index] = Some(text.sections[0].value.clone());
+        }
+        assert_eq!(shown, options.into_iter().map(Some).collect::<Vec<_>>());
+    }
+
+    const LANDSCAPE: f32 = 16.0 / 9.0;
+    const PORTRAIT: f32 = 9.0 / 16.0;
+
+    #[test]
+    fn grid_shape_fills_rows() {
+        assert_eq!(grid_shape(1, LANDSCAPE), (1, 1));
+        assert_eq!(grid_shape(4, LANDSCAPE), (2, 2));
+        assert_eq!(grid_shape(4, PORTRAIT), (2, 2));
+        assert_eq!(grid_shape(6, LANDSCAPE), (2, 3));
+        assert_eq!(grid_shape(6, PORTRAIT), (3, 2));
+    }
+
+    #[test]
+    fn grid_shape_keeps_prime_counts_roughly_square() {
+        assert_eq!(grid_shape(7, LANDSCAPE), (2, 4));
+        assert_eq!(grid_shape(7, PORTRAIT), (4, 2));
+        assert_eq!(grid_shape(13, LANDSCAPE), (2, 7));
+        assert_eq!(grid_shape(13, PORTRAIT), (7, 2));
+    }
+
+    #[test]
+    fn grid_shape_leaves_no_empty_row() {
+        for options in 0..=30 {
+            for aspect_ratio in [0.25, PORTRAIT, 1.0, LANDSCAPE, 4.0] {
+                let (rows, columns) = grid_shape(options, aspect_ratio);
+                assert!(rows * columns >= options.max(1));
+                assert!(rows * columns < options.max(1) + columns);
+            }
+        }
+    }
+}